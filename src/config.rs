@@ -0,0 +1,378 @@
+use toml::Spanned;
+
+/// User configuration, read from `$XDG_CONFIG_HOME/candela/config.toml`. Every field is optional.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Move files to the trash instead of deleting them, unless told otherwise on the command
+    /// line.
+    pub trash: bool,
+
+    /// Tweaks of the built-in project kinds, by name (`rust`, `npm`...).
+    pub builtin: std::collections::BTreeMap<Spanned<String>, BuiltinTweak>,
+
+    /// Project kinds declared by the user.
+    pub projects: Vec<ProjectKind>,
+
+    pub ccpp: CCppRules,
+
+    pub duplicates: DuplicatesRules,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuiltinTweak {
+    pub enabled: bool,
+    /// Replaces the marker files of the kind.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markers: Option<Vec<String>>,
+    /// Globs, relative to the project, of paths to remove on top of the built-in ones.
+    pub remove: Vec<Spanned<String>>,
+}
+
+impl Default for BuiltinTweak {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            markers: None,
+            remove: vec![],
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectKind {
+    /// Can't be the name of a built-in kind.
+    pub name: Spanned<String>,
+    /// A directory containing one of these files is a project of this kind.
+    pub markers: Vec<String>,
+    /// Globs, relative to the project, of paths to remove.
+    #[serde(default)]
+    pub remove: Vec<Spanned<String>>,
+    /// Command run in the project directory before removing anything, program first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Globs, relative to the project, of what the command removes. They are removed natively
+    /// when the program is missing, fails or times out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<Spanned<String>>,
+}
+
+/// Regexes matched against file names in C/C++ projects, tried before the built-in ones.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CCppRules {
+    pub deliverable: Vec<Spanned<String>>,
+    pub temporary: Vec<Spanned<String>>,
+    pub source: Vec<Spanned<String>>,
+}
+
+/// Added to the built-in rules of the duplicates walker.
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DuplicatesRules {
+    /// Directories with these names are not walked.
+    pub forbidden_dir_names: Vec<String>,
+    /// Directories containing a file with one of these names are not walked.
+    pub forbidden_dir_markers: Vec<String>,
+}
+
+/// An error in the configuration file, located by line and column.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl ConfigError {
+    fn at(content: &str, offset: usize, message: String) -> Self {
+        let before = &content[..offset.min(content.len())];
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message,
+        }
+    }
+}
+
+impl Config {
+    /// Where the configuration file is, None without a home directory to find it in.
+    pub fn path() -> Option<std::path::PathBuf> {
+        let mut path = crate::xdg::config_home()?;
+        path.push("candela");
+        path.push("config.toml");
+        Some(path)
+    }
+
+    /// Parse a configuration and check the values that the TOML schema can't: regexes, globs and
+    /// built-in names.
+    pub fn parse(content: &str) -> Result<Self, Vec<ConfigError>> {
+        let config: Self = toml::from_str(content).map_err(|err| {
+            let offset = err.span().map(|span| span.start).unwrap_or(0);
+            vec![ConfigError::at(content, offset, err.message().to_owned())]
+        })?;
+        let mut errors = config.check(content);
+        errors.sort_by_key(|err| (err.line, err.column));
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn check(&self, content: &str) -> Vec<ConfigError> {
+        let mut errors = vec![];
+        let known = crate::recursive_cleaner::builtin_project_names();
+        for (name, tweak) in &self.builtin {
+            if !known.contains(&name.get_ref().as_str()) {
+                errors.push(ConfigError::at(
+                    content,
+                    name.span().start,
+                    format!(
+                        "unknown built-in project kind \"{}\", expected one of {}",
+                        name.get_ref(),
+                        known.join(", ")
+                    ),
+                ));
+            }
+            check_globs(content, &tweak.remove, &mut errors);
+        }
+        for project in &self.projects {
+            if known.contains(&project.name.get_ref().as_str()) {
+                errors.push(ConfigError::at(
+                    content,
+                    project.name.span().start,
+                    format!(
+                        "\"{}\" is a built-in project kind, tweak it in [builtin.{}] instead",
+                        project.name.get_ref(),
+                        project.name.get_ref()
+                    ),
+                ));
+            }
+            check_globs(content, &project.remove, &mut errors);
+            check_globs(content, &project.fallback, &mut errors);
+        }
+        [
+            &self.ccpp.deliverable,
+            &self.ccpp.temporary,
+            &self.ccpp.source,
+        ]
+        .into_iter()
+        .flatten()
+        .for_each(|regex| {
+            if let Err(err) = regex::Regex::new(regex.get_ref()) {
+                errors.push(ConfigError::at(
+                    content,
+                    regex.span().start,
+                    err.to_string(),
+                ));
+            }
+        });
+        errors
+    }
+
+    /// Load the configuration file. A missing file gives the default configuration, an invalid
+    /// one is reported and ignored.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                return Self::default();
+            }
+        };
+        match Self::parse(&content) {
+            Ok(config) => config,
+            Err(errors) => {
+                errors
+                    .iter()
+                    .for_each(|err| eprintln!("{}: {}", path.display(), err));
+                eprintln!("{}: ignored, see `candela config validate`", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn builtin_tweak(&self, name: &str) -> Option<&BuiltinTweak> {
+        self.builtin.get(name)
+    }
+}
+
+/// Globs are relative to the project and must stay inside of it.
+fn check_globs(content: &str, globs: &[Spanned<String>], errors: &mut Vec<ConfigError>) {
+    globs.iter().for_each(|glob| {
+        let path = std::path::Path::new(glob.get_ref());
+        let message = if path.is_absolute() {
+            Some("globs must be relative to the project".to_owned())
+        } else if path
+            .components()
+            .any(|comp| comp == std::path::Component::ParentDir)
+        {
+            Some("globs can't go out of the project with \"..\"".to_owned())
+        } else {
+            glob::Pattern::new(glob.get_ref())
+                .err()
+                .map(|err| err.to_string())
+        };
+        if let Some(message) = message {
+            errors.push(ConfigError::at(content, glob.span().start, message));
+        }
+    });
+}
+
+/// Wrap a value that doesn't come from the configuration file, for the effective configuration.
+pub fn unspanned<T>(value: T) -> Spanned<T> {
+    Spanned::new(0..0, value)
+}
+
+// INFO: tests don't depend on the configuration of whoever runs them.
+static CONFIG: once_cell::sync::Lazy<Config> = once_cell::sync::Lazy::new(|| {
+    if cfg!(test) {
+        Config::default()
+    } else {
+        Config::load()
+    }
+});
+
+/// The configuration, loaded on first use.
+pub fn get() -> &'static Config {
+    &CONFIG
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Print the configuration merged with the built-in rules
+    Show,
+    /// Check the configuration file and report errors
+    Validate,
+}
+
+pub fn run(command: Command) {
+    match command {
+        Command::Show => show(),
+        Command::Validate => validate(),
+    }
+}
+
+/// Print the effective configuration, in the format of the configuration file.
+fn show() {
+    let config = get();
+    let effective = Config {
+        trash: config.trash,
+        builtin: crate::recursive_cleaner::effective_builtins(),
+        projects: config
+            .projects
+            .iter()
+            .map(|project| ProjectKind {
+                name: project.name.clone(),
+                markers: project.markers.clone(),
+                remove: project.remove.clone(),
+                command: project.command.clone(),
+                fallback: project.fallback.clone(),
+            })
+            .collect(),
+        ccpp: crate::recursive_cleaner::effective_ccpp_rules(),
+        duplicates: crate::duplicates::effective_rules(),
+    };
+    match toml::to_string(&effective) {
+        Ok(out) => print!("{out}"),
+        Err(err) => eprintln!("{err}"),
+    }
+}
+
+fn validate() {
+    let Some(path) = Config::path() else {
+        eprintln!("config: {}", crate::xdg::no_home());
+        std::process::exit(1);
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    match Config::parse(&content) {
+        Ok(_) => println!("{}: valid", path.display()),
+        Err(errors) => {
+            errors
+                .iter()
+                .for_each(|err| eprintln!("{}: {}", path.display(), err));
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use super::Config;
+
+    #[test]
+    fn custom_project() {
+        let config = Config::parse(
+            r#"
+            [[projects]]
+            name = "zig"
+            markers = ["build.zig"]
+            remove = ["zig-cache", "zig-out"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.projects[0].name.get_ref(), "zig");
+        assert_eq!(config.projects[0].remove.len(), 2);
+    }
+
+    #[test]
+    fn syntax_error_line() {
+        let errors = Config::parse("trash = true\ntrash = false\n").unwrap_err();
+        assert_eq!(errors[0].line, 2);
+    }
+
+    #[test]
+    fn invalid_regex_line() {
+        let errors =
+            Config::parse("[ccpp]\ntemporary = [\n  '.+\\.tmp$',\n  '(',\n]\n").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (4, 3));
+    }
+
+    #[test]
+    fn globs_out_of_the_project() {
+        let errors = Config::parse(
+            "[[projects]]\nname = \"x\"\nmarkers = [\"x.txt\"]\nremove = [\"../*\", \"/tmp/*\", \"out/../../y\", \"out\"]\n",
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|err| err.line == 4));
+    }
+
+    #[test]
+    fn project_named_as_builtin() {
+        let errors = Config::parse("[[projects]]\nname = \"rust\"\nmarkers = [\"Cargo.toml\"]\n")
+            .unwrap_err();
+        assert_eq!((errors[0].line, errors[0].column), (2, 8));
+    }
+
+    #[test]
+    fn unknown_builtin() {
+        let errors = Config::parse("[builtin.cobol]\nenabled = false\n").unwrap_err();
+        assert_eq!(errors[0].line, 1);
+    }
+}
@@ -1,11 +1,120 @@
-pub fn get_disk_usage<P>(path: P) -> u64
+use std::os::unix::fs::MetadataExt;
+
+/// Size of file trees, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct DiskUsage {
+    /// Sum of the file lengths, as `du --apparent-size` shows.
+    pub apparent: u64,
+    /// Space taken on disk, as `du` and `df` see it: whole blocks, holes of sparse files left out.
+    pub allocated: u64,
+}
+
+impl DiskUsage {
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self {
+            apparent: self.apparent.saturating_sub(rhs.apparent),
+            allocated: self.allocated.saturating_sub(rhs.allocated),
+        }
+    }
+}
+
+impl std::ops::AddAssign for DiskUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.apparent += rhs.apparent;
+        self.allocated += rhs.allocated;
+    }
+}
+
+/// Adds up the disk usage of several trees. Files that are hard-linked more than once, like in
+/// pnpm stores, are only counted the first time they are met, in any of the trees. Symlinks are
+/// never followed.
+#[derive(Debug, Default)]
+struct Counter {
+    seen: std::collections::HashSet<(u64, u64)>,
+    usage: DiskUsage,
+}
+
+impl Counter {
+    /// Count the tree at `path`. With `--one-file-system`, directories that are mount points of
+    /// another filesystem are left out.
+    fn add<P>(&mut self, path: P)
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let Ok(root_meta) = path.as_ref().symlink_metadata() else {
+            return;
+        };
+        let root_dev = root_meta.dev();
+        let one_file_system = crate::walk::options().one_file_system;
+        let walk_dir = crate::walk::walk_project(path.as_ref(), |_, _, _, _| {}).skip_hidden(false);
+        for meta in walk_dir
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.metadata().ok())
+        {
+            if one_file_system && meta.dev() != root_dev {
+                continue;
+            }
+            if !meta.is_dir() && meta.nlink() > 1 && !self.seen.insert((meta.dev(), meta.ino())) {
+                continue;
+            }
+            self.usage += DiskUsage {
+                apparent: meta.len(),
+                // INFO: st_blocks is in 512 bytes units whatever the block size.
+                allocated: meta.blocks() * 512,
+            };
+        }
+    }
+
+    fn usage(&self) -> DiskUsage {
+        self.usage
+    }
+}
+
+/// Disk usage of all the trees, counted with a single [`Counter`].
+pub fn get_disk_usage_of<I, P>(paths: I) -> DiskUsage
 where
+    I: IntoIterator<Item = P>,
     P: AsRef<std::path::Path>,
 {
-    jwalk::WalkDir::new(path)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter_map(|entry| entry.path().metadata().ok())
-        .map(|meta| meta.len())
-        .sum()
+    let mut counter = Counter::default();
+    paths.into_iter().for_each(|path| counter.add(path));
+    counter.usage()
+}
+
+#[cfg(test)]
+mod tests_counter {
+    use super::get_disk_usage_of;
+    use crate::fixture::Tree;
+
+    #[test]
+    fn hard_links_count_once() {
+        let tree = Tree::new();
+        let file = tree.file("a/file", &"x".repeat(10_000));
+        std::fs::create_dir_all(tree.path("b")).unwrap();
+        std::fs::hard_link(&file, tree.path("b/link")).unwrap();
+
+        let once = get_disk_usage_of([&file]);
+        assert_eq!(once.apparent, 10_000);
+        assert_eq!(get_disk_usage_of([file, tree.path("b/link")]), once);
+    }
+
+    #[test]
+    fn sparse_files_and_symlinks() {
+        let tree = Tree::new();
+        let sparse = tree.file("sparse", "");
+        std::fs::File::options()
+            .write(true)
+            .open(&sparse)
+            .unwrap()
+            .set_len(1 << 20)
+            .unwrap();
+        let usage = get_disk_usage_of([&sparse]);
+        assert_eq!(usage.apparent, 1 << 20);
+        assert!(usage.allocated < usage.apparent);
+
+        // INFO: the link is counted, not what it points to.
+        std::os::unix::fs::symlink(&sparse, tree.path("link")).unwrap();
+        assert!(get_disk_usage_of([tree.path("link")]).apparent < 1 << 20);
+    }
 }
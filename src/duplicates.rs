@@ -1,6 +1,6 @@
 mod walker;
 use rayon::prelude::*;
-use spinoff::{spinners, Spinner};
+use spinoff::spinners;
 use walker::*;
 
 #[derive(clap::Args, Debug)]
@@ -9,13 +9,20 @@ pub struct Cli {
     #[arg(short, long, value_hint = clap::ValueHint::DirPath)]
     pub output: Option<std::path::PathBuf>,
 
+    #[command(flatten)]
+    pub trash: crate::trash::TrashArgs,
+
+    #[command(flatten)]
+    pub walk: crate::walk::WalkArgs,
+
     #[arg(value_hint = clap::ValueHint::FilePath)]
     pub directories_to_scan: Vec<std::path::PathBuf>,
 }
 
 impl Cli {
     pub fn run(&self) {
-        let mut dw = DuplicatesWalker::new(false);
+        self.walk.apply();
+        let mut dw = DuplicatesWalker::new(crate::ui::options().quiet);
         let map = dw.make_filesize_map_for_paths(
             self.directories_to_scan
                 .iter()
@@ -34,7 +41,7 @@ impl Cli {
             return;
         }
         if confirm_deletion(&to_delete) {
-            perform_deletion(to_delete);
+            perform_deletion(to_delete, self.trash.deletion());
         }
     }
 }
@@ -44,25 +51,32 @@ fn confirm_deletion(to_delete: &std::collections::HashSet<std::path::PathBuf>) -
     ve.sort();
     println!("the following files will be deleted:");
     ve.iter().for_each(|file| println!("- {}", file.display()));
-    inquire::Confirm::new("Continue?")
-        .with_default(false)
-        .prompt()
-        .unwrap_or(false)
+    crate::ui::confirm("Continue?", false)
 }
 
-fn perform_deletion(mut to_delete: std::collections::HashSet<std::path::PathBuf>) {
+fn perform_deletion(
+    mut to_delete: std::collections::HashSet<std::path::PathBuf>,
+    deletion: crate::trash::Deletion,
+) {
     let results: Vec<_> = to_delete
         .par_drain()
         .map(|path| {
-            let res = std::fs::remove_file(&path);
-            (path, res)
+            let size = path.metadata().map(|meta| meta.len()).unwrap_or(0);
+            let res = deletion.remove(&path);
+            (path, size, res)
         })
         .collect();
-    results.iter().for_each(|(path, res)| {
-        if let Err(err) = res {
-            eprintln!("{}: {}", path.display(), err);
-        } else {
-            println!("removed {}", path.display());
+    let mut journal = crate::journal::Journal::new("duplicates");
+    let quiet = crate::ui::options().quiet;
+    results.into_iter().for_each(|(path, size, res)| match res {
+        Err(err) => eprintln!("{}: {}", path.display(), err),
+        Ok(trashed_to) => {
+            match &trashed_to {
+                _ if quiet => {}
+                Some(trashed) => println!("trashed {} to {}", path.display(), trashed.display()),
+                None => println!("removed {}", path.display()),
+            }
+            journal.record_removal(&path, size, trashed_to);
         }
     });
 }
@@ -70,6 +84,16 @@ fn perform_deletion(mut to_delete: std::collections::HashSet<std::path::PathBuf>
 fn ask_for_what_files_to_delete(
     groups: MatchingFilesGroups,
 ) -> Option<std::collections::HashSet<std::path::PathBuf>> {
+    // INFO: what to delete can't be guessed, every file is kept, that is also what --yes takes.
+    let ui = crate::ui::options();
+    if ui.yes {
+        eprintln!("--yes keeps every file, no duplicates are selected for deletion");
+        return None;
+    }
+    if !ui.input {
+        eprintln!("no input, no duplicates are selected for deletion");
+        return None;
+    }
     let mut to_delete = std::collections::HashSet::<std::path::PathBuf>::new();
     for group in groups.as_string_iters() {
         let path_strs: Vec<_> = group.collect();
@@ -82,12 +106,10 @@ fn ask_for_what_files_to_delete(
         match ans {
             Ok(selected) => {
                 if selected.len() != len
-                    || inquire::Confirm::new(
+                    || crate::ui::confirm(
                         "You are about to delete all file in set. Are you sure?",
+                        false,
                     )
-                    .with_default(false)
-                    .prompt()
-                    .unwrap_or(false)
                 {
                     selected
                         .iter()
@@ -112,10 +134,10 @@ fn ask_for_what_files_to_delete(
 }
 
 fn output(groups: &MatchingFilesGroups, filepath: &std::path::Path) {
-    let mut spinner = Spinner::new(
+    let mut spinner = crate::ui::Progress::new(
         spinners::Dots,
         format!("Serialising to \"{}\"", filepath.display()),
-        None,
+        spinoff::Streams::Stdout,
     );
     if let Err(err) = groups.to_file(filepath) {
         spinner.fail(&format!(
@@ -127,3 +149,11 @@ fn output(groups: &MatchingFilesGroups, filepath: &std::path::Path) {
         spinner.success(&format!("Serialised to \"{}\"", filepath.display()));
     }
 }
+
+/// Walker rules with the configuration applied, for `config show`.
+pub fn effective_rules() -> crate::config::DuplicatesRules {
+    crate::config::DuplicatesRules {
+        forbidden_dir_names: forbidden_dir_names().map(str::to_owned).collect(),
+        forbidden_dir_markers: forbidden_dir_file_markers().map(str::to_owned).collect(),
+    }
+}
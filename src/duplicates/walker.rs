@@ -1,12 +1,14 @@
+use crate::ui::Progress;
 use multimap::MultiMap;
 use rayon::prelude::*;
-use spinoff::{spinners, Spinner};
+use spinoff::spinners;
 use std::ffi::OsStr;
 use std::io::Read;
+use std::os::unix::fs::MetadataExt;
 
 pub struct DuplicatesWalker {
     quiet: bool,
-    spinner: Option<Spinner>,
+    spinner: Option<Progress>,
 }
 
 impl DuplicatesWalker {
@@ -22,8 +24,16 @@ impl DuplicatesWalker {
         paths: impl Iterator<Item = &'a std::path::Path>,
     ) -> MultiMap<u64, std::path::PathBuf> {
         if !self.quiet {
-            self.spinner = Some(Spinner::new(spinners::Cute, "Walking", None));
+            self.spinner = Some(Progress::new(
+                spinners::Cute,
+                "Walking",
+                spinoff::Streams::Stdout,
+            ));
         }
+        // INFO: when following symlinks, a file can be reached through several paths, it must
+        // not be taken for a duplicate of itself.
+        let follow_symlinks = crate::walk::options().follow_symlinks;
+        let mut seen = std::collections::HashSet::new();
         let file_by_sizes: MultiMap<u64, std::path::PathBuf> = paths
             .flat_map(|path| {
                 if let Some(spinner) = &mut self.spinner {
@@ -34,6 +44,11 @@ impl DuplicatesWalker {
                     .into_iter()
                     .filter_map(|dir_ent_res| dir_ent_res.ok())
                     .filter(|dir_ent| dir_ent.path().is_file())
+                    .filter(|dir_ent| {
+                        !follow_symlinks
+                            || std::fs::metadata(dir_ent.path())
+                                .is_ok_and(|meta| seen.insert((meta.dev(), meta.ino())))
+                    })
                     .map(|dir_ent| (dir_ent.client_state, dir_ent.path()))
                     .collect::<Vec<_>>()
             })
@@ -52,10 +67,10 @@ impl DuplicatesWalker {
         file_by_sizes: &MultiMap<u64, std::path::PathBuf>,
     ) -> MatchingFilesGroups {
         if !self.quiet {
-            self.spinner = Some(Spinner::new(
+            self.spinner = Some(Progress::new(
                 spinners::Cute,
                 "Scanning content, making groups...",
-                None,
+                spinoff::Streams::Stdout,
             ));
         }
         let mut out = MatchingFilesGroups::from_size_groups(file_by_sizes);
@@ -181,16 +196,19 @@ fn group_same_content<'a>(
 
 /// Create a walkdir where dirent are associated with the file sizes.
 fn make_walkdir(dir: &std::path::Path) -> jwalk::WalkDirGeneric<(usize, u64)> {
-    jwalk::WalkDirGeneric::<(usize, u64)>::new(dir)
-        .skip_hidden(false)
-        .process_read_dir(|_depth, _path, _rd_state, children| {
+    crate::walk::walk_dir(
+        dir,
+        crate::walk::Scope::Search,
+        |_depth, _path, _rd_state, children| {
             if contains_forbidden_marker(children) {
                 children.clear();
             } else {
                 do_not_enter_forbidden_dir(children);
                 retain_not_hidden_and_add_size_on_state(children);
             }
-        })
+        },
+    )
+    .skip_hidden(false)
 }
 
 /// Removed hidden files from yielded files and add file len to their client_state for further
@@ -212,14 +230,21 @@ fn retain_not_hidden_and_add_size_on_state(
     });
 }
 
-static FORBIDDEN_DIR_NAMES: once_cell::sync::Lazy<[&'static OsStr; 3]> =
-    once_cell::sync::Lazy::new(|| {
-        [
-            OsStr::new("node_modules"),
-            OsStr::new("delivery"),
-            OsStr::new(".git"),
-        ]
-    });
+static BUILTIN_FORBIDDEN_DIR_NAMES: [&str; 3] = ["node_modules", "delivery", ".git"];
+
+/// Built-in names followed by the ones from the configuration.
+pub fn forbidden_dir_names() -> impl Iterator<Item = &'static str> {
+    BUILTIN_FORBIDDEN_DIR_NAMES.iter().copied().chain(
+        crate::config::get()
+            .duplicates
+            .forbidden_dir_names
+            .iter()
+            .map(String::as_str),
+    )
+}
+
+static FORBIDDEN_DIR_NAMES: once_cell::sync::Lazy<Vec<&'static OsStr>> =
+    once_cell::sync::Lazy::new(|| forbidden_dir_names().map(OsStr::new).collect());
 
 /// Some children dirs are deemed out of bounds by their name. This sets their read_children_path
 /// to None so that jwalk does not traverse them.
@@ -236,16 +261,27 @@ fn do_not_enter_forbidden_dir(
         });
 }
 
-static FORBIDDEN_DIR_FILE_MARKERS: once_cell::sync::Lazy<[&'static OsStr; 5]> =
-    once_cell::sync::Lazy::new(|| {
-        [
-            OsStr::new(".git"),
-            OsStr::new("Cargo.toml"),
-            OsStr::new("Cargo.lock"),
-            OsStr::new("package.json"),
-            OsStr::new(".ignore-dups.flag"),
-        ]
-    });
+static BUILTIN_FORBIDDEN_DIR_FILE_MARKERS: [&str; 5] = [
+    ".git",
+    "Cargo.toml",
+    "Cargo.lock",
+    "package.json",
+    ".ignore-dups.flag",
+];
+
+/// Built-in markers followed by the ones from the configuration.
+pub fn forbidden_dir_file_markers() -> impl Iterator<Item = &'static str> {
+    BUILTIN_FORBIDDEN_DIR_FILE_MARKERS.iter().copied().chain(
+        crate::config::get()
+            .duplicates
+            .forbidden_dir_markers
+            .iter()
+            .map(String::as_str),
+    )
+}
+
+static FORBIDDEN_DIR_FILE_MARKERS: once_cell::sync::Lazy<Vec<&'static OsStr>> =
+    once_cell::sync::Lazy::new(|| forbidden_dir_file_markers().map(OsStr::new).collect());
 
 /// Some folders are deemed out of bounds depending on if they contain a flag or something that
 /// indicates they are code repositories. This sets all read_children_path to None so that walkir doesn't go any deeper in this directory.
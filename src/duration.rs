@@ -0,0 +1,57 @@
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+
+/// Parse a duration written as a number followed by a unit: `h` for hours, `d` for days, `w` for
+/// weeks, `m` for months (30 days) and `y` for years (365 days). For example `30d` or `6m`.
+pub fn parse(input: &str) -> Result<std::time::Duration, String> {
+    let input = input.trim();
+    let Some(unit) = input.chars().last() else {
+        return Err("empty duration".to_owned());
+    };
+    let number = &input[..input.len() - unit.len_utf8()];
+    let seconds_per_unit = match unit {
+        'h' => HOUR,
+        'd' => DAY,
+        'w' => 7 * DAY,
+        'm' => 30 * DAY,
+        'y' => 365 * DAY,
+        _ => {
+            return Err(format!(
+                "unknown unit '{unit}' in \"{input}\", expected one of h, d, w, m, y"
+            ))
+        }
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("\"{input}\" doesn't start with a number"))?;
+    let seconds = number
+        .checked_mul(seconds_per_unit)
+        .ok_or_else(|| format!("\"{input}\" is too long"))?;
+    Ok(std::time::Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use super::parse;
+
+    #[test]
+    fn days() {
+        assert_eq!(parse("30d"), Ok(std::time::Duration::from_secs(30 * 86400)));
+    }
+
+    #[test]
+    fn months() {
+        assert_eq!(parse("6m"), Ok(std::time::Duration::from_secs(180 * 86400)));
+    }
+
+    #[test]
+    fn unknown_unit() {
+        assert!(parse("6x").is_err());
+    }
+
+    #[test]
+    fn missing_number() {
+        assert!(parse("d").is_err());
+        assert!(parse("").is_err());
+    }
+}
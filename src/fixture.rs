@@ -0,0 +1,55 @@
+//! Directory trees for tests, under the temporary directory and removed once dropped.
+
+static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+pub struct Tree {
+    pub root: std::path::PathBuf,
+}
+
+impl Tree {
+    pub fn new() -> Self {
+        let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("candela-test-{}-{}", std::process::id(), n));
+        std::fs::create_dir_all(&root).unwrap();
+        // INFO: the temporary directory can be a symlink (macOS), paths are compared to the
+        // canonical ones.
+        Self {
+            root: root.canonicalize().unwrap(),
+        }
+    }
+
+    pub fn path(&self, rel: &str) -> std::path::PathBuf {
+        self.root.join(rel)
+    }
+
+    /// Create the file and its parents.
+    pub fn file(&self, rel: &str, content: &str) -> std::path::PathBuf {
+        let path = self.path(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+}
+
+pub fn set_modified(path: &std::path::Path, time: std::time::SystemTime) {
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+/// Set the last access of the file or directory.
+pub fn set_accessed(path: &std::path::Path, time: std::time::SystemTime) {
+    std::fs::File::open(path)
+        .unwrap()
+        .set_times(std::fs::FileTimes::new().set_accessed(time))
+        .unwrap();
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,315 @@
+use std::io::{BufRead, Write};
+
+/// First line of a journal file, describing the run.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub timestamp: String,
+    pub subcommand: String,
+}
+
+/// Every other line of a journal file, one per destructive operation.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    /// A file or directory that was deleted, or moved to `trashed_to`.
+    Removed {
+        path: std::path::PathBuf,
+        size: u64,
+        trashed_to: Option<std::path::PathBuf>,
+    },
+    /// An external command whose side effects are out of our hands.
+    Command {
+        command: String,
+        cwd: std::path::PathBuf,
+    },
+}
+
+/// Records what a run deletes in `$XDG_STATE_HOME/candela/<run-id>.jsonl`, as JSON lines so that
+/// everything done so far is kept if the run is interrupted. The file is only created with the
+/// first entry.
+pub struct Journal {
+    info: RunInfo,
+    file: Option<std::fs::File>,
+}
+
+fn journal_dir() -> std::io::Result<std::path::PathBuf> {
+    crate::xdg::state_home()
+        .map(|dir| dir.join("candela"))
+        .ok_or_else(crate::xdg::no_home)
+}
+
+fn journal_path(run_id: &str) -> std::io::Result<std::path::PathBuf> {
+    Ok(journal_dir()?.join(format!("{run_id}.jsonl")))
+}
+
+/// Whether `run_id` looks like the ids given to runs, `<date>-<time>-<pid>`, so that it can't
+/// point outside of the journal directory.
+fn is_run_id(run_id: &str) -> bool {
+    let parts: Vec<&str> = run_id.split('-').collect();
+    matches!(parts.as_slice(), [date, time, pid]
+        if date.len() == 8 && time.len() == 6 && !pid.is_empty()
+            && parts.iter().all(|part| part.bytes().all(|byte| byte.is_ascii_digit())))
+}
+
+impl Journal {
+    pub fn new(subcommand: &str) -> Self {
+        let now = chrono::Local::now();
+        Self {
+            info: RunInfo {
+                run_id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), std::process::id()),
+                timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+                subcommand: subcommand.to_owned(),
+            },
+            file: None,
+        }
+    }
+
+    /// Id of the run, once something was recorded.
+    pub fn run_id(&self) -> Option<&str> {
+        self.file.as_ref().map(|_| self.info.run_id.as_str())
+    }
+
+    pub fn record_removal(
+        &mut self,
+        path: &std::path::Path,
+        size: u64,
+        trashed_to: Option<std::path::PathBuf>,
+    ) {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+        self.record(&Entry::Removed {
+            path,
+            size,
+            trashed_to,
+        });
+    }
+
+    pub fn record_command(&mut self, cmd: &std::process::Command) {
+        let mut command = cmd.get_program().to_string_lossy().into_owned();
+        cmd.get_args().for_each(|arg| {
+            command.push(' ');
+            command.push_str(&arg.to_string_lossy());
+        });
+        let cwd = cmd
+            .get_current_dir()
+            .and_then(|dir| std::path::absolute(dir).ok())
+            .unwrap_or_default();
+        self.record(&Entry::Command { command, cwd });
+    }
+
+    fn record(&mut self, entry: &Entry) {
+        if self.file.is_none() {
+            self.file = self.create_file();
+        }
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        if let Err(err) = write_line(file, entry) {
+            eprintln!("journal: {}", err);
+        }
+    }
+
+    fn create_file(&self) -> Option<std::fs::File> {
+        let res = journal_dir().and_then(|dir| {
+            std::fs::create_dir_all(dir)?;
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(journal_path(&self.info.run_id)?)?;
+            write_line(&mut file, &self.info)?;
+            Ok(file)
+        });
+        match res {
+            Ok(file) => Some(file),
+            Err(err) => {
+                eprintln!("journal: {}", err);
+                None
+            }
+        }
+    }
+}
+
+fn write_line<T: serde::Serialize>(file: &mut std::fs::File, value: &T) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    file.write_all(&line)
+}
+
+/// Read back a journal file, lines that can't be parsed are skipped.
+fn read_journal(path: &std::path::Path) -> std::io::Result<(RunInfo, Vec<Entry>)> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut lines = file.lines();
+    let first = lines.next().unwrap_or_else(|| Ok(String::new()))?;
+    let info: RunInfo = serde_json::from_str(&first)?;
+    let entries = lines
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    Ok((info, entries))
+}
+
+/// List past runs, oldest first.
+pub fn print_history() {
+    let journal_dir = match journal_dir() {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("history: {}", err);
+            return;
+        }
+    };
+    let Ok(read_dir) = std::fs::read_dir(journal_dir) else {
+        println!("No recorded runs.");
+        return;
+    };
+    let mut runs: Vec<(RunInfo, Vec<Entry>)> = read_dir
+        .filter_map(|dir_ent| dir_ent.ok())
+        .map(|dir_ent| dir_ent.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| read_journal(&path).ok())
+        .collect();
+    if runs.is_empty() {
+        println!("No recorded runs.");
+        return;
+    }
+    runs.sort_by(|(a, _), (b, _)| a.timestamp.cmp(&b.timestamp));
+    println!(
+        "{:<24}  {:<25}  {:<10}  {:>7}  {:>10}  TRASHED",
+        "RUN ID", "DATE", "COMMAND", "ENTRIES", "SIZE"
+    );
+    for (info, entries) in &runs {
+        let (size, trashed) = entries
+            .iter()
+            .fold((0, 0), |(size, trashed), entry| match entry {
+                Entry::Removed {
+                    size: entry_size,
+                    trashed_to,
+                    ..
+                } => (size + entry_size, trashed + trashed_to.is_some() as usize),
+                Entry::Command { .. } => (size, trashed),
+            });
+        println!(
+            "{:<24}  {:<25}  {:<10}  {:>7}  {:>10}  {}",
+            info.run_id,
+            info.timestamp,
+            info.subcommand,
+            entries.len(),
+            humansize::format_size(size, humansize::DECIMAL),
+            trashed
+        );
+    }
+}
+
+/// Put back everything from a run that is still in the trash, and tell what can't be. Exits with
+/// an error unless everything was restored.
+pub fn undo(run_id: &str) {
+    if !is_run_id(run_id) {
+        eprintln!("run {}: not a run id, see `candela history`", run_id);
+        std::process::exit(1);
+    }
+    let (info, entries) = match journal_path(run_id).and_then(|path| read_journal(&path)) {
+        Ok(journal) => journal,
+        Err(err) => {
+            eprintln!("run {}: {}", run_id, err);
+            std::process::exit(1);
+        }
+    };
+    let mut n_restored: usize = 0;
+    let mut not_restored: Vec<String> = vec![];
+    for entry in entries.iter().rev() {
+        match entry {
+            Entry::Removed {
+                path,
+                trashed_to: Some(trashed),
+                ..
+            } => match crate::trash::restore(trashed, path) {
+                Ok(()) => {
+                    println!("restored {}", path.display());
+                    n_restored += 1;
+                }
+                Err(err) => not_restored.push(format!("{}: {}", path.display(), err)),
+            },
+            Entry::Removed {
+                path,
+                trashed_to: None,
+                ..
+            } => not_restored.push(format!("{}: deleted for good", path.display())),
+            Entry::Command { command, cwd } => not_restored.push(format!(
+                "`{}` in {}: effects of commands can't be undone",
+                command,
+                cwd.display()
+            )),
+        }
+    }
+    println!(
+        "Restored {} out of {} entries from {} run {}.",
+        n_restored,
+        entries.len(),
+        info.subcommand,
+        info.run_id
+    );
+    if !not_restored.is_empty() {
+        println!("Could not restore:");
+        not_restored
+            .iter()
+            .for_each(|reason| println!("- {}", reason));
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests_journal {
+    use super::*;
+    use crate::fixture::Tree;
+
+    #[test]
+    fn run_ids() {
+        assert!(is_run_id("20240301-102030-4242"));
+        assert!(!is_run_id("../20240301-102030-4242"));
+        assert!(!is_run_id("20240301-102030-"));
+        assert!(!is_run_id("20240301-102030-42/.."));
+        assert!(!is_run_id("2024031-102030-4242"));
+    }
+
+    #[test]
+    fn read_back() {
+        let tree = Tree::new();
+        let path = tree.path("run.jsonl");
+        let mut file = std::fs::File::create(&path).unwrap();
+        let info = RunInfo {
+            run_id: "20240301-102030-4242".to_owned(),
+            timestamp: "2024-03-01T10:20:30+01:00".to_owned(),
+            subcommand: "clean".to_owned(),
+        };
+        write_line(&mut file, &info).unwrap();
+        write_line(
+            &mut file,
+            &Entry::Removed {
+                path: "/repo/target".into(),
+                size: 4096,
+                trashed_to: Some("/home/me/.local/share/Trash/files/target".into()),
+            },
+        )
+        .unwrap();
+        file.write_all(b"{\"kind\": \"unknown\"}\n").unwrap();
+        write_line(
+            &mut file,
+            &Entry::Command {
+                command: "make clean".to_owned(),
+                cwd: "/repo".into(),
+            },
+        )
+        .unwrap();
+
+        let (read_info, entries) = read_journal(&path).unwrap();
+        assert_eq!(read_info.run_id, info.run_id);
+        assert_eq!(read_info.subcommand, "clean");
+        // INFO: lines that can't be parsed are skipped.
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            &entries[0],
+            Entry::Removed { size: 4096, trashed_to: Some(trashed), .. }
+                if trashed.ends_with("Trash/files/target")
+        ));
+        assert!(matches!(&entries[1], Entry::Command { command, .. } if command == "make clean"));
+    }
+}
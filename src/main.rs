@@ -1,20 +1,25 @@
+mod config;
 pub mod disk_usage;
 mod duplicates;
+mod duration;
+#[cfg(test)]
+mod fixture;
 mod flattener;
+mod journal;
 mod recursive_cleaner;
 mod template;
+mod trash;
+mod ui;
+mod walk;
+mod xdg;
 
 use clap::{CommandFactory, Parser};
 
 fn main() {
     let opt = Cli::parse();
+    opt.ui.apply();
     match opt.command {
-        Commands::Clean {
-            mut base_dirs,
-            force,
-        } => {
-            recursive_cleaner::RecursiveCleaner::new(force).run(base_dirs.drain(..));
-        }
+        Commands::Clean(cli) => cli.run(),
         Commands::Flatten(flatten_opt) => flattener::run(flatten_opt),
         Commands::Version => {
             print!("{CANDLE_ASCII_ART}\n\n");
@@ -27,6 +32,10 @@ fn main() {
             shell.generate(&mut Cli::command(), &mut std::io::stdout());
         }
         Commands::Duplicates(cli) => cli.run(),
+        Commands::Du(cli) => cli.run(),
+        Commands::Config { command } => config::run(command),
+        Commands::History => journal::print_history(),
+        Commands::Undo { run_id } => journal::undo(&run_id),
     }
 }
 
@@ -53,18 +62,15 @@ jgs  `-._       _.-'
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    #[command(flatten)]
+    ui: ui::UiArgs,
 }
 
 #[derive(Debug, clap::Subcommand)]
 enum Commands {
     #[command(arg_required_else_help = true)]
-    Clean {
-        #[arg(short, long)]
-        force: bool,
-
-        #[arg(value_hint = clap::ValueHint::DirPath)]
-        base_dirs: Vec<String>,
-    },
+    Clean(recursive_cleaner::Cli),
     Flatten(flattener::Cli),
     Version,
     Template(template::TemplateCli),
@@ -73,4 +79,17 @@ enum Commands {
         shell: clap_complete_command::Shell,
     },
     Duplicates(duplicates::Cli),
+    /// List projects with their size, reclaimable space and last activity
+    Du(recursive_cleaner::du::Cli),
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        command: config::Command,
+    },
+    /// List the past runs of clean and duplicates
+    History,
+    /// Restore what a past run moved to the trash
+    Undo {
+        run_id: String,
+    },
 }
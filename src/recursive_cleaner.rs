@@ -1,59 +1,534 @@
+mod activity;
+mod caches;
+mod command;
+pub mod du;
 mod find_project_files;
 mod is_elf;
+mod plan;
 mod project;
+mod report;
 
-use spinoff::{spinners, Spinner};
+use crate::disk_usage::{get_disk_usage_of, DiskUsage};
+use report::{CacheReport, ProjectReport, Status};
+use spinoff::spinners;
+use std::io::Write;
+
+#[derive(clap::Args, Debug)]
+#[command()]
+pub struct Cli {
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Only report what would be removed and how much space it would free
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+
+    /// Scan everything first, then pick the projects to clean from a list
+    #[arg(short, long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+
+    /// Skip projects that had activity more recently than this (e.g. 30d, 6m)
+    #[arg(long, value_parser = crate::duration::parse)]
+    pub older_than: Option<std::time::Duration>,
+
+    /// Leave git commits and fetches out of the last activity of projects
+    #[arg(long)]
+    pub no_git_activity: bool,
+
+    /// Keep looking for projects inside of projects, for monorepos and workspaces
+    #[arg(long)]
+    pub nested: bool,
+
+    /// Prune the global caches of toolchains too, entirely or only the entries not accessed for
+    /// AGE (e.g. --caches=90d)
+    #[arg(
+        long,
+        value_name = "AGE",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = crate::duration::parse
+    )]
+    pub caches: Option<Option<std::time::Duration>>,
+
+    /// What to remove from the target directory of Rust projects
+    #[arg(long, value_enum, default_value_t)]
+    pub rust_target: plan::TargetCleaning,
+
+    /// Kill cleanup commands that run for longer than this, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = 600)]
+    pub command_timeout: u64,
+
+    /// Remove the deliverables of C/C++ projects too, libraries and what their Makefile builds
+    #[arg(long)]
+    pub remove_deliverables: bool,
+
+    /// Write a JSON report of the run to this file
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    pub report: Option<std::path::PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: report::Format,
+
+    #[command(flatten)]
+    pub trash: crate::trash::TrashArgs,
+
+    #[command(flatten)]
+    pub walk: crate::walk::WalkArgs,
+
+    #[arg(value_hint = clap::ValueHint::DirPath)]
+    pub base_dirs: Vec<String>,
+}
+
+impl Cli {
+    pub fn run(self) {
+        if let Err(err) = self.check_base_dirs() {
+            eprintln!("candela clean: {err}");
+            std::process::exit(2);
+        }
+        self.walk.apply();
+        RecursiveCleaner::new(&self).run(self.base_dirs.into_iter());
+    }
+
+    /// Report the base directories that don't exist. An age given to `--caches` without `=` ends
+    /// up among them, and is refused rather than emptying the caches whole.
+    fn check_base_dirs(&self) -> Result<(), String> {
+        for dir in &self.base_dirs {
+            if std::path::Path::new(dir).exists() {
+                continue;
+            }
+            if self.caches.is_some() && crate::duration::parse(dir).is_ok() {
+                return Err(format!(
+                    "\"{dir}\" is not a directory, write --caches={dir} to only prune what \
+                     wasn't accessed for {dir}"
+                ));
+            }
+            eprintln!("candela clean: {dir}: no such directory");
+        }
+        Ok(())
+    }
+}
 
 pub struct RecursiveCleaner {
-    spinner: spinoff::Spinner,
-    n_processed: u32,
-    n_cleaned: u32,
-    usage_difference: u64,
+    spinner: crate::ui::Progress,
+    spinner_stream: spinoff::Streams,
+    report: report::RunReport,
+    report_path: Option<std::path::PathBuf>,
+    format: report::Format,
     force: bool,
+    dry_run: bool,
+    interactive: bool,
+    older_than: Option<std::time::Duration>,
+    git_activity: bool,
+    nested: bool,
+    caches: Option<Option<std::time::Duration>>,
+    command_timeout: std::time::Duration,
+    plan_options: plan::Options,
+    deletion: crate::trash::Deletion,
+    journal: crate::journal::Journal,
 }
 
 impl RecursiveCleaner {
-    pub fn new(force: bool) -> Self {
+    pub fn new(cli: &Cli) -> Self {
+        // INFO: keep stdout for the JSON report.
+        let spinner_stream = match cli.format {
+            report::Format::Human => spinoff::Streams::Stdout,
+            report::Format::Json => spinoff::Streams::Stderr,
+        };
         Self {
-            spinner: Spinner::new(spinners::BouncingBar, "Scaning and deleting", None),
-            n_processed: 0,
-            n_cleaned: 0,
-            usage_difference: 0,
-            force,
+            spinner: new_spinner(spinner_stream),
+            spinner_stream,
+            report: report::RunReport {
+                dry_run: cli.dry_run,
+                ..Default::default()
+            },
+            report_path: cli.report.clone(),
+            format: cli.format,
+            force: cli.force,
+            dry_run: cli.dry_run,
+            interactive: cli.interactive,
+            older_than: cli.older_than,
+            git_activity: !cli.no_git_activity,
+            nested: cli.nested,
+            caches: cli.caches,
+            command_timeout: std::time::Duration::from_secs(cli.command_timeout),
+            plan_options: plan::Options {
+                rust_target: cli.rust_target,
+                remove_deliverables: cli.remove_deliverables,
+            },
+            deletion: cli.trash.deletion(),
+            journal: crate::journal::Journal::new("clean"),
         }
     }
     fn restart_spinner(&mut self) {
-        self.spinner = Spinner::new(spinners::BouncingBar, "Scaning and deleting", None);
+        self.spinner = new_spinner(self.spinner_stream);
     }
 
     pub fn run<T>(&mut self, paths_to_search: T)
     where
         T: Iterator<Item = String>,
     {
-        for arg in paths_to_search {
-            find_project_files::iter(&arg)
-                .filter_map(|direntry| direntry.client_state.map(|state| (direntry, state)))
-                .for_each(|(direntry, state)| {
-                    let mut path = direntry.path();
-                    path.pop();
-                    if let Some(size_difference) = self.clean_project_at_path(&path, state) {
-                        self.usage_difference += size_difference;
-                        self.n_cleaned += 1;
+        let mut paths_to_search = paths_to_search.peekable();
+        let only_caches = self.caches.is_some() && paths_to_search.peek().is_none();
+        if only_caches {
+            self.spinner.clear();
+        } else if self.dry_run {
+            self.dry_run(paths_to_search);
+        } else if self.interactive {
+            self.interactive(paths_to_search);
+        } else {
+            for arg in paths_to_search {
+                for project in find_projects(&arg, self.nested) {
+                    self.report.n_processed += 1;
+                    let plan = project.plan(&self.plan_options);
+                    if !self.is_too_recent(&plan) {
+                        self.clean_project_at_path(plan);
+                    }
+                }
+            }
+            self.spinner_success();
+        }
+        if let Some(older_than) = self.caches {
+            self.prune_caches(older_than);
+        }
+        self.output_report();
+    }
+
+    /// Measure every known cache then prune them once the user agreed, or only measure them on
+    /// dry runs.
+    fn prune_caches(&mut self, older_than: Option<std::time::Duration>) {
+        self.restart_spinner();
+        let mut caches = vec![];
+        let mut reports = vec![];
+        for cache in caches::known() {
+            self.spinner
+                .update_text(format!("Measuring the {} cache", cache.name));
+            let prunable = cache.prunable(older_than);
+            let cache_report = CacheReport {
+                name: cache.name.to_owned(),
+                path: cache.path.clone(),
+                status: match (self.dry_run, prunable.is_empty()) {
+                    (true, _) => Status::Planned,
+                    (false, true) => Status::NothingToClean,
+                    (false, false) => Status::Declined,
+                },
+                size_before: cache.size(),
+                reclaimable: get_disk_usage_of(&prunable),
+                size_after: None,
+                n_removed: 0,
+                errors: vec![],
+            };
+            caches.push((cache, prunable));
+            reports.push(cache_report);
+        }
+        let to_prune = reports
+            .iter()
+            .any(|report| report.status == Status::Declined);
+        let mut is_listed = false;
+        if self.dry_run || !to_prune {
+            self.spinner
+                .success("Measured caches, nothing was deleted.");
+        } else if !self.confirm_caches(&reports) {
+            self.spinner.success("Left the caches untouched.");
+            // INFO: already listed before asking, unless quiet.
+            is_listed = true;
+        } else {
+            let mut freed = DiskUsage::default();
+            for ((cache, prunable), cache_report) in caches.iter().zip(&mut reports) {
+                if prunable.is_empty() {
+                    continue;
+                }
+                self.spinner
+                    .update_text(format!("Pruning the {} cache", cache.name));
+                for path in prunable {
+                    match self.delete(path) {
+                        Ok(()) => cache_report.n_removed += 1,
+                        Err(err) => {
+                            eprintln!("\rclean cache: {}: {}", path.display(), err);
+                            cache_report
+                                .errors
+                                .push(format!("{}: {}", path.display(), err));
+                        }
                     }
-                    self.n_processed += 1;
-                });
+                }
+                let size_after = cache.size();
+                freed += cache_report.size_before.saturating_sub(size_after);
+                cache_report.size_after = Some(size_after);
+                cache_report.status = Status::Cleaned;
+            }
+            self.report.usage_difference += freed;
+            self.spinner.success(&format!(
+                "Pruned caches. Saved {}",
+                humansize::format_size(freed.allocated, humansize::DECIMAL)
+            ));
+        }
+        self.report.caches = reports;
+        if self.format == report::Format::Human && !is_listed {
+            let _ = report::print_caches(&self.report.caches, &mut std::io::stdout());
+        }
+    }
+
+    /// List the caches with what pruning them would free and tell if the user agrees. They are
+    /// agreed to all at once.
+    fn confirm_caches(&mut self, caches: &[CacheReport]) -> bool {
+        self.spinner.stop();
+        let _ = write!(self.spinner_stream.get_stream(), "\r");
+        let ui = crate::ui::options();
+        // INFO: quiet runs still show the caches when the user is actually asked.
+        if !ui.quiet || (ui.input && !ui.yes && !self.force) {
+            // INFO: stdout is kept for the JSON report.
+            let _ = match self.format {
+                report::Format::Human => print_caches_to_prune(caches, &mut std::io::stdout()),
+                report::Format::Json => print_caches_to_prune(caches, &mut std::io::stderr()),
+            };
+        }
+        let ans = self.force || crate::ui::confirm("prune these caches ?", false);
+        self.restart_spinner();
+        ans
+    }
+
+    /// Write the report where asked to.
+    fn output_report(&mut self) {
+        self.report.run_id = self.journal.run_id().map(str::to_owned);
+        if let Some(path) = &self.report_path {
+            if let Err(err) = self.report.write(path) {
+                eprintln!("report: {}: {}", path.display(), err);
+            }
+        }
+        if self.format == report::Format::Json {
+            match self.report.to_json() {
+                Ok(json) => println!("{json}"),
+                Err(err) => eprintln!("report: {}", err),
+            }
+        }
+    }
+
+    /// Tells if the project had activity more recently than `--older-than` allows, in which case
+    /// it is reported as skipped. The artefacts of the plan are left out of the activity.
+    fn is_too_recent(&mut self, plan: &plan::Plan) -> bool {
+        let Some(older_than) = self.older_than else {
+            return false;
+        };
+        let Some(last_activity) =
+            activity::last_activity(&plan.path, plan.artefact_paths(), self.git_activity)
+        else {
+            return false;
+        };
+        let idle_for = last_activity.elapsed().unwrap_or_default();
+        if idle_for < older_than {
+            self.report
+                .projects
+                .push(ProjectReport::new(plan, Status::SkippedRecent));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Walk like a normal run would, but only compute the plans. Removals already planned for an
+    /// earlier project are left out of the later ones. Plans that would free something are
+    /// returned along with how much, sorted by decreasing size, the others are reported.
+    fn scan<T>(&mut self, paths_to_search: T) -> Vec<(DiskUsage, plan::Plan)>
+    where
+        T: Iterator<Item = String>,
+    {
+        let mut sized: Vec<(DiskUsage, plan::Plan)> = vec![];
+        let mut claimed = std::collections::HashSet::new();
+        for arg in paths_to_search {
+            for project in find_projects(&arg, self.nested) {
+                self.report.n_processed += 1;
+                let mut plan = project.plan(&self.plan_options);
+                plan.retain_unclaimed(&mut claimed);
+                if self.is_too_recent(&plan) {
+                    continue;
+                }
+                if plan.frees_something() {
+                    sized.push((plan.reclaimable(), plan));
+                } else {
+                    self.report
+                        .projects
+                        .push(ProjectReport::new(&plan, Status::NothingToClean));
+                }
+            }
+        }
+        sized.sort_by(|(size_a, plan_a), (size_b, plan_b)| {
+            (size_b.allocated)
+                .cmp(&size_a.allocated)
+                .then_with(|| plan_a.path.cmp(&plan_b.path))
+        });
+        sized
+    }
+
+    /// Report a plan that is not executed, along with how much it would free.
+    fn report_unexecuted(&mut self, size: DiskUsage, plan: &plan::Plan, status: Status) {
+        let mut project = ProjectReport::new(plan, status);
+        project.reclaimable = Some(size);
+        self.report.projects.push(project);
+    }
+
+    /// Walk like a normal run would, but only compute the plans and print them.
+    fn dry_run<T>(&mut self, paths_to_search: T)
+    where
+        T: Iterator<Item = String>,
+    {
+        self.spinner.update_text("Scaning (dry run)");
+        let sized = self.scan(paths_to_search);
+        sized
+            .iter()
+            .for_each(|(size, plan)| self.report_unexecuted(*size, plan, Status::Planned));
+        self.spinner.success(&format!(
+            "Scanned {} project folders, nothing was deleted.",
+            self.report.n_processed
+        ));
+        if self.format == report::Format::Human {
+            self.print_skipped_recent();
+            plan::print_report(&sized);
+        }
+    }
+
+    /// Scan everything, then let the user tick the projects to clean in a single list sorted by
+    /// reclaimable size. Ticked projects are cleaned without further confirmation.
+    fn interactive<T>(&mut self, paths_to_search: T)
+    where
+        T: Iterator<Item = String>,
+    {
+        self.spinner.update_text("Scaning");
+        let sized = self.scan(paths_to_search);
+        self.spinner.stop();
+        let _ = write!(self.spinner_stream.get_stream(), "\r");
+        if sized.is_empty() {
+            self.spinner_success();
+            return;
+        }
+        let lang_width = sized
+            .iter()
+            .map(|(_, plan)| plan.langs_display().len())
+            .max()
+            .unwrap_or(0);
+        let options: Vec<String> = sized
+            .iter()
+            .map(|(size, plan)| {
+                let last_activity = activity::format_date(activity::last_activity(
+                    &plan.path,
+                    plan.artefact_paths(),
+                    self.git_activity,
+                ));
+                format!(
+                    "{:>10}  {:<lang_width$}  {:<10}  {}",
+                    humansize::format_size(size.allocated, humansize::DECIMAL),
+                    plan.langs_display(),
+                    last_activity,
+                    plan.path.display()
+                )
+            })
+            .collect();
+        let ui = crate::ui::options();
+        // INFO: no project is selected by default, that is what --yes takes.
+        let selected: std::collections::HashSet<usize> = if ui.yes {
+            eprintln!("--yes takes the default selection, no projects are selected");
+            std::collections::HashSet::new()
+        } else if !ui.input {
+            eprintln!("no input, no projects are selected");
+            std::collections::HashSet::new()
+        } else {
+            match inquire::MultiSelect::new("Projects to clean:", options)
+                .with_page_size(15)
+                .raw_prompt()
+            {
+                Ok(selected) => selected.into_iter().map(|option| option.index).collect(),
+                Err(_) => std::collections::HashSet::new(),
+            }
+        };
+        self.restart_spinner();
+        for (idx, (size, plan)) in sized.into_iter().enumerate() {
+            if selected.contains(&idx) {
+                self.clean_plan(plan);
+            } else {
+                self.report_unexecuted(size, &plan, Status::NotSelected);
+            }
         }
         self.spinner_success();
     }
 
+    fn print_skipped_recent(&self) {
+        let skipped: Vec<_> = self.report.with_status(Status::SkippedRecent).collect();
+        if skipped.is_empty() {
+            return;
+        }
+        println!("Skipped {} recently active projects:", skipped.len());
+        skipped
+            .iter()
+            .for_each(|project| println!("- {}", project.path.display()));
+    }
+
     fn spinner_success(&mut self) {
-        if self.n_cleaned != 0 {
+        self.spinner_summary();
+        if self.format == report::Format::Human && !crate::ui::options().quiet {
+            self.print_skipped_recent();
+            self.print_fallbacks();
+        }
+    }
+
+    /// List the commands that didn't do the cleaning themselves, and why.
+    fn print_fallbacks(&self) {
+        let fallbacks: Vec<_> = self
+            .report
+            .projects
+            .iter()
+            .flat_map(|project| {
+                project
+                    .commands
+                    .iter()
+                    .filter(|command| command.execution != report::Execution::Tool)
+                    .map(move |command| (project, command))
+            })
+            .collect();
+        if fallbacks.is_empty() {
+            return;
+        }
+        println!("Cleanup commands that didn't do the cleaning:");
+        for (project, command) in fallbacks {
+            let outcome = match command.execution {
+                report::Execution::Native => "removed natively",
+                report::Execution::Skipped => "not run, it has no fallback to trash",
+                _ => "nothing removed",
+            };
+            println!(
+                "- `{}` in {}: {}, {}",
+                command.command,
+                project.path.display(),
+                command.reason.as_deref().unwrap_or_default(),
+                outcome
+            );
+        }
+    }
+
+    fn spinner_summary(&mut self) {
+        let n_failed = self.report.with_status(Status::Failed).count();
+        if n_failed != 0 {
+            self.spinner.fail(
+                format!(
+                    "Cleaned {} out of {} projects, failed to clean {}. Saved {}",
+                    self.report.n_cleaned,
+                    self.report.n_processed,
+                    n_failed,
+                    humansize::format_size(
+                        self.report.usage_difference.allocated,
+                        humansize::DECIMAL
+                    )
+                )
+                .as_ref(),
+            );
+        } else if self.report.n_cleaned != 0 {
             self.spinner.success(
                 format!(
                     "Cleaned {} out of {} projects. Saved {} Thanks for using me!",
-                    self.n_cleaned,
-                    self.n_processed,
-                    humansize::format_size(self.usage_difference, humansize::DECIMAL)
+                    self.report.n_cleaned,
+                    self.report.n_processed,
+                    humansize::format_size(
+                        self.report.usage_difference.allocated,
+                        humansize::DECIMAL
+                    )
                 )
                 .as_ref(),
             );
@@ -61,102 +536,102 @@ impl RecursiveCleaner {
             self.spinner.success(
                 format!(
                     "Scanned {} project folders, nothing to clean. Thanks for using me!",
-                    self.n_processed
+                    self.report.n_processed
                 )
                 .as_ref(),
             );
         }
     }
 
-    fn clean_project_at_path(
-        &mut self,
-        path: &std::path::Path,
-        state: find_project_files::ProjectLang,
-    ) -> Option<u64> {
+    /// Clean a project with a single plan, covering every kind it was detected as.
+    fn clean_project_at_path(&mut self, plan: plan::Plan) {
+        if plan.actions.is_empty() {
+            self.report
+                .projects
+                .push(ProjectReport::new(&plan, Status::NothingToClean));
+        } else if plan.needs_confirmation() && !self.confirm_plan(&plan) {
+            self.report
+                .projects
+                .push(ProjectReport::new(&plan, Status::Declined));
+        } else {
+            self.clean_plan(plan);
+        }
+    }
+
+    /// Execute the plan, measure how much it freed and report it.
+    fn clean_plan(&mut self, plan: plan::Plan) {
+        let mut project = ProjectReport::new(&plan, Status::NothingToClean);
+        let measured = plan.measured_paths();
+        let before_clean_size = get_disk_usage_of(&measured);
+        if self.execute_plan(plan, &mut project) {
+            let after_clean_size = get_disk_usage_of(&measured);
+            project.status = Status::Cleaned;
+            project.size_before = Some(before_clean_size);
+            project.size_after = Some(after_clean_size);
+            self.report.usage_difference += before_clean_size.saturating_sub(after_clean_size);
+            self.report.n_cleaned += 1;
+        } else if !project.errors.is_empty() {
+            project.status = Status::Failed;
+        }
+        self.report.projects.push(project);
+    }
+
+    /// Execute every action of the plan, returns true if something was removed. Paths that could
+    /// not be removed don't count.
+    fn execute_plan(&mut self, plan: plan::Plan, project: &mut ProjectReport) -> bool {
         let mut has_cleaned_something = false;
-        let before_clean_size = super::disk_usage::get_disk_usage(path);
-        match state {
-            find_project_files::ProjectLang::Yarn => {
-                let node_modules_path = {
-                    let mut tmp = path.to_owned();
-                    tmp.push("node_modules");
-                    tmp
-                };
-                let yarn_install_state_path = {
-                    let mut tmp = path.to_owned();
-                    tmp.push(".yarn");
-                    tmp.push("install-state.gz");
-                    tmp
-                };
-                let yarn_unpluged_path = {
-                    let mut tmp = path.to_owned();
-                    tmp.push(".yarn");
-                    tmp.push("unplugged");
-                    tmp
-                };
-                let mut cmd = std::process::Command::new("yarn");
-                cmd.arg("cache").arg("clean").current_dir(path);
-                self.spawn_and_wait_command(cmd);
-                if yarn_install_state_path.exists() {
-                    if let Err(err) = std::fs::remove_file(yarn_install_state_path) {
-                        eprintln!("1 {}", err);
+        for action in plan.actions {
+            match action {
+                plan::Action::Remove(path) => {
+                    // INFO: a command earlier in the plan may have removed it already.
+                    if path.symlink_metadata().is_ok() {
+                        has_cleaned_something |= self.remove(&path, project);
                     }
-                    has_cleaned_something = true;
                 }
-                if yarn_unpluged_path.exists() {
-                    if let Err(err) = std::fs::remove_dir_all(yarn_unpluged_path) {
-                        eprintln!("2 {}", err);
-                    }
-                    has_cleaned_something = true;
-                }
-                if node_modules_path.exists() {
-                    if let Err(err) = std::fs::remove_dir_all(node_modules_path) {
-                        eprintln!("3 {}", err);
-                    }
-                    has_cleaned_something = true;
-                }
-            }
-            find_project_files::ProjectLang::Npm => {
-                let mut nm_path = path.to_owned();
-                nm_path.push("node_modules");
-                if nm_path.exists() {
-                    if let Err(err) = std::fs::remove_dir_all(&nm_path) {
-                        eprintln!("clean project: {}: {}", nm_path.display(), err);
-                    }
-                    has_cleaned_something = true;
-                }
-                let mut dist_path = path.to_owned();
-                dist_path.push("dist");
-                if dist_path.is_dir() {
-                    if let Err(err) = std::fs::remove_dir_all(&dist_path) {
-                        eprintln!("clean project: {}: {}", dist_path.display(), err);
+                plan::Action::Command {
+                    program,
+                    args,
+                    frees,
+                    installed,
+                } => {
+                    let mut cmd = std::process::Command::new(program);
+                    cmd.args(args).current_dir(&plan.path);
+                    let mut command = report::CommandReport::new(&cmd);
+                    // INFO: what a command deletes can't be recovered, when trashing we move
+                    // what it would free ourselves, or don't run it at all.
+                    let reason = if self.deletion == crate::trash::Deletion::Trash {
+                        Some("trashing".to_owned())
+                    } else if !installed {
+                        Some("not installed".to_owned())
+                    } else {
+                        self.spawn_and_wait_command(cmd, &mut command).err()
+                    };
+                    let mut has_freed = !frees.is_empty();
+                    if let Some(reason) = reason {
+                        if frees.is_empty() && self.deletion == crate::trash::Deletion::Trash {
+                            command.execution = report::Execution::Skipped;
+                        } else if frees.is_empty() {
+                            command.execution = report::Execution::Failed;
+                            project
+                                .errors
+                                .push(format!("{}: {}", command.command, reason));
+                        } else {
+                            command.execution = report::Execution::Native;
+                            // INFO: a failed command may have removed some of it already.
+                            for path in frees.iter().filter(|path| path.symlink_metadata().is_ok())
+                            {
+                                self.remove(path, project);
+                            }
+                            has_freed = frees.iter().any(|path| path.symlink_metadata().is_err());
+                        }
+                        command.reason = Some(reason);
                     }
-                    has_cleaned_something = true;
-                }
-            }
-            find_project_files::ProjectLang::Rust => {
-                let target_path = {
-                    let mut tmp = path.to_owned();
-                    tmp.push("target");
-                    tmp
-                };
-                if target_path.exists() {
-                    let mut cmd = std::process::Command::new("cargo");
-                    cmd.arg("clean").current_dir(path);
-                    self.spawn_and_wait_command(cmd);
-                    has_cleaned_something = true;
+                    has_cleaned_something |= has_freed;
+                    project.commands.push(command);
                 }
             }
-            find_project_files::ProjectLang::CCpp => {
-                has_cleaned_something = self.process_unix_ccpp(path);
-            }
-        }
-        let after_clean_size = super::disk_usage::get_disk_usage(path);
-        if has_cleaned_something {
-            Some(before_clean_size - after_clean_size)
-        } else {
-            None
         }
+        has_cleaned_something
     }
 
     // fn sort_and_print_paths(aa: impl Iterator<Item = &std::path::PathBuf>) {
@@ -166,70 +641,357 @@ impl RecursiveCleaner {
     //     file_names.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
     // }
 
-    fn process_unix_ccpp(&mut self, path: &std::path::Path) -> bool {
-        let project = project::Project::from_c_project_path(path);
-        let to_remove: Vec<_> = project
-            .files
-            .iter()
-            .filter(|entry| {
-                matches!(
-                    entry.client_state,
-                    project::FileKind::Temporary | project::FileKind::OtherElf
-                )
-            })
-            .map(|direntry| direntry.path())
-            .collect();
-        if !to_remove.is_empty() {
-            self.spinner.stop();
-            print!("\r");
-            project.pretty_print();
-            println!("about to remove:");
-            // TODO: case insentive sort
-            to_remove.iter().for_each(|path| {
-                if let Some(os_file_name) = path.file_name() {
-                    if let Some(file_name) = os_file_name.to_str() {
-                        println!("{}", file_name);
-                    } else {
-                        eprintln!("Failed to convert one filename to str");
-                    }
-                } else {
-                    eprintln!("Failed to extract a filename from path");
-                }
-            });
-            let ans = if self.force {
+    /// Delete or trash `path`, and record it in the journal.
+    fn delete(&mut self, path: &std::path::Path) -> std::io::Result<()> {
+        let size = get_disk_usage_of([path]).allocated;
+        let trashed_to = self.deletion.remove(path)?;
+        self.journal.record_removal(path, size, trashed_to);
+        Ok(())
+    }
+
+    /// Delete or trash `path` for the project, returns true if it is gone.
+    fn remove(&mut self, path: &std::path::Path, project: &mut ProjectReport) -> bool {
+        match self.delete(path) {
+            Ok(()) => {
+                project.removed.push(path.to_owned());
                 true
-            } else {
-                inquire::Confirm::new("proceed ?")
-                    .with_default(true)
-                    .prompt()
-                    .unwrap_or(false)
+            }
+            Err(err) => {
+                eprintln!("\rclean project: {}: {}", path.display(), err);
+                project.errors.push(format!("{}: {}", path.display(), err));
+                false
+            }
+        }
+    }
+
+    /// Show what is about to be removed from the project and tell if the user agrees.
+    fn confirm_plan(&mut self, plan: &plan::Plan) -> bool {
+        self.spinner.stop();
+        let _ = write!(self.spinner_stream.get_stream(), "\r");
+        let ui = crate::ui::options();
+        // INFO: quiet runs still show the plan when the user is actually asked.
+        if !ui.quiet || (ui.input && !ui.yes && !self.force) {
+            // INFO: stdout is kept for the JSON report.
+            let _ = match self.format {
+                report::Format::Human => print_plan(plan, &mut std::io::stdout()),
+                report::Format::Json => print_plan(plan, &mut std::io::stderr()),
             };
+        }
+        let ans = self.ask_confirmation();
+        self.restart_spinner();
+        ans
+    }
+
+    fn ask_confirmation(&self) -> bool {
+        self.force || crate::ui::confirm("proceed ?", true)
+    }
+
+    /// Run the command and fill its report, tells why it didn't do its job if it didn't.
+    fn spawn_and_wait_command(
+        &mut self,
+        mut cmd: std::process::Command,
+        command: &mut report::CommandReport,
+    ) -> Result<(), String> {
+        self.journal.record_command(&cmd);
+        let output = command::output_with_timeout(&mut cmd, self.command_timeout)
+            .map_err(|err| err.to_string())?;
+        command.stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let Some(status) = output.status else {
+            self.spinner.fail("cleanup command timed out");
             self.restart_spinner();
-            if ans {
-                to_remove.iter().for_each(|path| {
-                    if let Err(err) = std::fs::remove_file(path) {
-                        eprintln!("\r{}", err);
-                    }
-                });
-                return true;
-            }
+            return Err(format!(
+                "timed out after {}s",
+                self.command_timeout.as_secs()
+            ));
+        };
+        command.exit_status = status.code();
+        if status.success() {
+            return Ok(());
         }
-        false
-    }
-    fn spawn_and_wait_command(&mut self, mut cmd: std::process::Command) {
-        if let Ok(output) = cmd.output() {
-            if !output.status.success() {
-                self.spinner.fail("failed cleanup command");
-                eprintln!(
-                    "{} exited with status: {}",
-                    cmd.get_program().to_str().unwrap_or("?"),
-                    output.status
-                );
-                use std::io::Write;
-                std::io::stderr().write_all(&output.stdout).unwrap();
-                std::io::stderr().write_all(&output.stderr).unwrap();
-                self.restart_spinner();
-            }
+        self.spinner.fail("failed cleanup command");
+        eprintln!(
+            "{} exited with status: {}",
+            cmd.get_program().to_str().unwrap_or("?"),
+            status
+        );
+        std::io::stderr().write_all(&output.stdout).unwrap();
+        std::io::stderr().write_all(&output.stderr).unwrap();
+        self.restart_spinner();
+        Err(match status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => status.to_string(),
+        })
+    }
+}
+
+fn print_caches_to_prune(
+    caches: &[CacheReport],
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    writeln!(out, "about to prune:")?;
+    report::print_caches(caches, out)
+}
+
+/// Describe the project and list the actions of the plan.
+fn print_plan(plan: &plan::Plan, out: &mut impl std::io::Write) -> std::io::Result<()> {
+    if let Some(project) = &plan.c_project {
+        project.pretty_print(out)?;
+    } else {
+        writeln!(
+            out,
+            "- {} project {}",
+            plan.langs_display(),
+            plan.path.display()
+        )?;
+    }
+    writeln!(out, "about to:")?;
+    plan.actions
+        .iter()
+        .try_for_each(|action| writeln!(out, "{}", action))
+}
+
+fn new_spinner(stream: spinoff::Streams) -> crate::ui::Progress {
+    crate::ui::Progress::new(spinners::BouncingBar, "Scaning and deleting", stream)
+}
+
+/// A project found by walking.
+struct Found {
+    path: std::path::PathBuf,
+    langs: Vec<find_project_files::ProjectLang>,
+    /// CMake build trees configured from the project that live outside of it.
+    build_trees: Vec<std::path::PathBuf>,
+}
+
+impl Found {
+    fn plan(&self, options: &plan::Options) -> plan::Plan {
+        plan::Plan::for_project(&self.path, &self.langs, options)
+            .with_build_trees(&self.build_trees)
+    }
+}
+
+/// Projects found by walking `dir`, along with their kind. CMake build trees met on the way go
+/// with the project they were configured from, rather than being projects of their own.
+fn find_projects(dir: &str, nested: bool) -> Vec<Found> {
+    let mut found = vec![];
+    let mut build_trees = vec![];
+    for direntry in find_project_files::iter(dir, nested) {
+        let mut path = direntry.path();
+        path.pop();
+        if direntry.file_name() == find_project_files::CMAKE_CACHE {
+            build_trees.push(path.clone());
         }
+        if !direntry.client_state.is_empty() {
+            found.push(Found {
+                path,
+                langs: direntry.client_state,
+                build_trees: vec![],
+            });
+        }
+    }
+    let mut attached = vec![];
+    for tree in build_trees {
+        let Some(project) = found.iter_mut().find(|project| {
+            project.path != tree
+                && project
+                    .langs
+                    .contains(&find_project_files::ProjectLang::CMake)
+                && plan::configured_from(&tree, &project.path)
+        }) else {
+            continue;
+        };
+        // INFO: the plan of the project finds its in-tree build trees itself.
+        if !tree.starts_with(&project.path) {
+            project.build_trees.push(tree.clone());
+        }
+        attached.push(tree);
+    }
+    found.retain(|project| !attached.contains(&project.path));
+    found
+}
+
+/// Names of the built-in project kinds, as used in the configuration file.
+pub fn builtin_project_names() -> Vec<&'static str> {
+    find_project_files::ProjectLang::BUILTIN
+        .iter()
+        .map(|lang| lang.config_name())
+        .collect()
+}
+
+/// Built-in project kinds with the configuration applied, for `config show`.
+pub fn effective_builtins(
+) -> std::collections::BTreeMap<toml::Spanned<String>, crate::config::BuiltinTweak> {
+    let config = crate::config::get();
+    find_project_files::ProjectLang::BUILTIN
+        .iter()
+        .map(|lang| {
+            let name = lang.config_name();
+            let tweak = config.builtin_tweak(name);
+            let effective = crate::config::BuiltinTweak {
+                enabled: tweak.is_none_or(|tweak| tweak.enabled),
+                markers: Some(
+                    find_project_files::markers_of(*lang)
+                        .into_iter()
+                        .map(|marker| marker.into_owned())
+                        .collect(),
+                ),
+                remove: tweak.map(|tweak| tweak.remove.clone()).unwrap_or_default(),
+            };
+            (crate::config::unspanned(name.to_owned()), effective)
+        })
+        .collect()
+}
+
+/// File name rules of C/C++ projects with the configuration applied, for `config show`.
+pub fn effective_ccpp_rules() -> crate::config::CCppRules {
+    project::effective_ccpp_rules()
+}
+
+#[cfg(test)]
+mod tests_find_projects {
+    use super::{find_project_files::ProjectLang, find_projects, plan};
+    use crate::fixture::Tree;
+
+    /// What each project found under `root` would remove, like a scan plans it.
+    fn planned(
+        root: &std::path::Path,
+        nested: bool,
+    ) -> Vec<(
+        std::path::PathBuf,
+        std::collections::HashSet<std::path::PathBuf>,
+    )> {
+        let mut claimed = std::collections::HashSet::new();
+        find_projects(root.to_str().unwrap(), nested)
+            .into_iter()
+            .map(|project| {
+                let mut plan = project.plan(&plan::Options::default());
+                plan.retain_unclaimed(&mut claimed);
+                (project.path, plan.artefact_paths())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested_projects_claim_their_artefacts_once() {
+        let tree = Tree::new();
+        tree.file("ws/Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        tree.file("ws/crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+        tree.file("ws/target/debug/core", "");
+        tree.file("ws/web/package-lock.json", "{}");
+        tree.file("ws/web/node_modules/left-pad/index.js", "");
+        // INFO: artefact directories are not searched for projects.
+        tree.file("ws/web/node_modules/left-pad/package-lock.json", "{}");
+
+        assert_eq!(
+            planned(&tree.root, false),
+            vec![(tree.path("ws"), [tree.path("ws/target")].into())]
+        );
+        assert_eq!(
+            planned(&tree.root, true),
+            vec![
+                (tree.path("ws"), [tree.path("ws/target")].into()),
+                // INFO: the target directory of the workspace, already claimed.
+                (tree.path("ws/crates/core"), [].into()),
+                (
+                    tree.path("ws/web"),
+                    [tree.path("ws/web/node_modules")].into()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn several_kinds_make_one_project() {
+        let tree = Tree::new();
+        tree.file("app/Cargo.toml", "[package]\nname = \"app\"\n");
+        tree.file("app/target/debug/app", "");
+        tree.file("app/package-lock.json", "{}");
+        tree.file("app/yarn.lock", "");
+        tree.file("app/node_modules/left-pad/index.js", "");
+
+        let found = find_projects(tree.root.to_str().unwrap(), false);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].langs,
+            vec![ProjectLang::Rust, ProjectLang::Npm, ProjectLang::Yarn]
+        );
+        let plan = found[0].plan(&plan::Options::default());
+        // INFO: node_modules is removed once, for both npm and Yarn.
+        assert_eq!(plan.actions.len(), 2);
+        assert_eq!(
+            plan.artefact_paths(),
+            [tree.path("app/target"), tree.path("app/node_modules")].into()
+        );
+    }
+
+    #[test]
+    fn out_of_source_build_tree() {
+        let tree = Tree::new();
+        let project = tree.path("lib");
+        tree.file("lib/CMakeLists.txt", "project(lib)\n");
+        tree.file(
+            "lib-build/CMakeCache.txt",
+            &format!("CMAKE_HOME_DIRECTORY:INTERNAL={}\n", project.display()),
+        );
+        tree.file("lib-build/Makefile", "all:\n");
+        tree.file("lib-build/CMakeFiles/Makefile2", "all:\n");
+
+        let found = find_projects(tree.root.to_str().unwrap(), false);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, project);
+        assert_eq!(found[0].build_trees, vec![tree.path("lib-build")]);
+        let plan = found[0].plan(&plan::Options::default());
+        assert_eq!(plan.artefact_paths(), [tree.path("lib-build")].into());
+
+        tree.file("lib-build/install/.candela-keep", "");
+        tree.file("lib-build/install/lib/liblib.so", "");
+        let plan = found[0].plan(&plan::Options::default());
+        assert_eq!(
+            plan.artefact_paths(),
+            [
+                tree.path("lib-build/CMakeCache.txt"),
+                tree.path("lib-build/CMakeFiles"),
+                tree.path("lib-build/Makefile"),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn build_tree_of_another_project() {
+        let tree = Tree::new();
+        tree.file("lib/CMakeLists.txt", "project(lib)\n");
+        tree.file(
+            "other-build/CMakeCache.txt",
+            "CMAKE_HOME_DIRECTORY:INTERNAL=/nowhere/other\n",
+        );
+        tree.file("other-build/Makefile", "all:\n");
+
+        let found = find_projects(tree.root.to_str().unwrap(), false);
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|project| project.build_trees.is_empty()));
+    }
+
+    #[test]
+    fn kept_directories() {
+        let tree = Tree::new();
+        tree.file("c/Makefile", "all:\n");
+        tree.file("c/main.o", "");
+        tree.file("c/vendor/.candela-keep", "");
+        tree.file("c/vendor/lib/lib.o", "");
+        tree.file("app/Cargo.toml", "[package]\nname = \"app\"\n");
+        tree.file("app/target/debug/app", "");
+        tree.file("app/target/release/.candela-keep", "");
+        tree.file("app/target/release/app", "");
+        tree.file("old/.candela-keep", "");
+        tree.file("old/Cargo.toml", "[package]\nname = \"old\"\n");
+        tree.file("old/target/debug/old", "");
+
+        assert_eq!(
+            planned(&tree.root, false),
+            vec![
+                (tree.path("app"), [tree.path("app/target/debug")].into()),
+                (tree.path("c"), [tree.path("c/main.o")].into()),
+            ]
+        );
     }
 }
@@ -0,0 +1,88 @@
+use super::plan::NOT_WALKED;
+
+/// Files of a git repository that get touched by commits, checkouts and fetches.
+static GIT_ACTIVITY_FILES: [&str; 3] = ["HEAD", "logs/HEAD", "FETCH_HEAD"];
+
+/// Last time something happened in a project: the newest modification time among its files,
+/// leaving out the `excluded` artefacts, and of its git refs if it is a repository and `git` is
+/// set.
+pub fn last_activity(
+    path: &std::path::Path,
+    excluded: std::collections::HashSet<std::path::PathBuf>,
+    git: bool,
+) -> Option<std::time::SystemTime> {
+    let excluded = std::sync::Arc::new(excluded);
+    let not_walked = excluded.clone();
+    let newest_file =
+        crate::walk::walk_project(path, move |_depth, _path, _read_dir_state, children| {
+            children
+                .iter_mut()
+                .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+                .filter(|dir_ent| dir_ent.file_type().is_dir())
+                .filter(|dir_ent| {
+                    NOT_WALKED.iter().any(|name| dir_ent.file_name() == *name)
+                        || not_walked.contains(&dir_ent.path())
+                })
+                .for_each(|dir_ent| dir_ent.read_children_path = None);
+        })
+        .skip_hidden(false)
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.file_type().is_file() && !excluded.contains(&dir_ent.path()))
+        .filter_map(|dir_ent| dir_ent.metadata().ok()?.modified().ok())
+        .max();
+    if git {
+        newest_file.max(last_git_activity(path))
+    } else {
+        newest_file
+    }
+}
+
+/// Day of an activity, for listings.
+pub fn format_date(time: Option<std::time::SystemTime>) -> String {
+    time.map(|time| {
+        chrono::DateTime::<chrono::Local>::from(time)
+            .format("%Y-%m-%d")
+            .to_string()
+    })
+    .unwrap_or_else(|| "-".to_owned())
+}
+
+fn last_git_activity(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    let git_dir = path.join(".git");
+    if !git_dir.is_dir() {
+        return None;
+    }
+    let refs = jwalk::WalkDir::new(git_dir.join("refs"))
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.file_type().is_file())
+        .map(|dir_ent| dir_ent.path());
+    GIT_ACTIVITY_FILES
+        .iter()
+        .map(|file| git_dir.join(file))
+        .chain(refs)
+        .filter_map(|file| file.metadata().ok()?.modified().ok())
+        .max()
+}
+
+#[cfg(test)]
+mod tests_last_activity {
+    use super::last_activity;
+    use crate::fixture::{set_modified, Tree};
+
+    #[test]
+    fn git_activity_is_optional() {
+        let tree = Tree::new();
+        let long_ago = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        set_modified(&tree.file("src/main.c", "int main;\n"), long_ago);
+        // INFO: artefacts don't count.
+        tree.file("build/main.o", "");
+        tree.file(".git/HEAD", "ref: refs/heads/main\n");
+        let excluded = [tree.path("build")].into();
+
+        assert_eq!(last_activity(&tree.root, excluded, false), Some(long_ago));
+        let excluded = [tree.path("build")].into();
+        assert!(last_activity(&tree.root, excluded, true).unwrap() > long_ago);
+    }
+}
@@ -0,0 +1,197 @@
+//! Global caches of toolchains and package managers, shared by every project. They are found and
+//! pruned without the tools, which may not even be installed anymore.
+
+use crate::disk_usage::{get_disk_usage_of, DiskUsage};
+
+/// What can be pruned of a cache when it is pruned by age.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Entries {
+    /// The files and directories this deep below the cache, that only make sense as a whole (an
+    /// extracted crate, a clone).
+    AtDepth(usize),
+    /// Every file of the cache on its own, for content-addressable stores.
+    Files,
+}
+
+#[derive(Debug)]
+pub struct Cache {
+    pub name: &'static str,
+    pub path: std::path::PathBuf,
+    entries: Entries,
+}
+
+fn env_path(var: &str) -> Option<std::path::PathBuf> {
+    std::env::var_os(var)
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// The caches known to candela that exist on this machine. Without a home directory, only the
+/// ones that environment variables point to can be found.
+pub fn known() -> Vec<Cache> {
+    let cargo_home = super::plan::cargo_home();
+    let cache_home = crate::xdg::cache_home();
+    let in_cache_home = |name: &str| cache_home.as_ref().map(|dir| dir.join(name));
+    let npm_cache = env_path("npm_config_cache").or_else(|| Some(crate::xdg::home()?.join(".npm")));
+    [
+        (
+            "Cargo registry",
+            // INFO: cache/<registry>/<crate>.crate and src/<registry>/<crate>/.
+            cargo_home.as_ref().map(|dir| dir.join("registry")),
+            Entries::AtDepth(3),
+        ),
+        (
+            "Cargo git",
+            // INFO: db/<repository>/ and checkouts/<repository>/.
+            cargo_home.as_ref().map(|dir| dir.join("git")),
+            Entries::AtDepth(2),
+        ),
+        (
+            "npm",
+            npm_cache.map(|dir| dir.join("_cacache")),
+            Entries::Files,
+        ),
+        (
+            "Yarn classic",
+            // INFO: v6/<package>/.
+            env_path("YARN_CACHE_FOLDER").or_else(|| in_cache_home("yarn")),
+            Entries::AtDepth(2),
+        ),
+        (
+            "pnpm store",
+            crate::xdg::data_home().map(|dir| dir.join("pnpm").join("store")),
+            Entries::Files,
+        ),
+        (
+            "pip",
+            env_path("PIP_CACHE_DIR").or_else(|| in_cache_home("pip")),
+            Entries::Files,
+        ),
+        (
+            "Go build",
+            env_path("GOCACHE").or_else(|| in_cache_home("go-build")),
+            Entries::Files,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, path, entries)| {
+        Some(Cache {
+            name,
+            path: path?,
+            entries,
+        })
+    })
+    .filter(|cache| cache.path.is_dir())
+    .collect()
+}
+
+/// Last time something was read in the tree at `path`.
+fn last_access(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    crate::walk::walk_project(path, |_, _, _, _| {})
+        .skip_hidden(false)
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| !dir_ent.file_type().is_dir())
+        .filter_map(|dir_ent| dir_ent.metadata().ok()?.accessed().ok())
+        .max()
+}
+
+impl Cache {
+    pub fn size(&self) -> DiskUsage {
+        get_disk_usage_of([&self.path])
+    }
+
+    /// What to remove to prune the cache: all of it, or only the entries that were not accessed
+    /// for `older_than`.
+    pub fn prunable(&self, older_than: Option<std::time::Duration>) -> Vec<std::path::PathBuf> {
+        let Some(older_than) = older_than else {
+            return vec![self.path.clone()];
+        };
+        let is_old = |path: &std::path::Path| {
+            last_access(path)
+                .is_some_and(|accessed| accessed.elapsed().unwrap_or_default() >= older_than)
+        };
+        let walk_dir = crate::walk::walk_project(&self.path, |_, _, _, _| {}).skip_hidden(false);
+        let walk_dir = match self.entries {
+            Entries::AtDepth(depth) => walk_dir.min_depth(depth).max_depth(depth),
+            Entries::Files => walk_dir,
+        };
+        walk_dir
+            .into_iter()
+            .filter_map(|dir_ent| dir_ent.ok())
+            .filter(|dir_ent| self.entries != Entries::Files || !dir_ent.file_type().is_dir())
+            .map(|dir_ent| dir_ent.path())
+            .filter(|path| is_old(path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests_prunable {
+    use super::*;
+    use crate::fixture::{set_accessed, Tree};
+
+    const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+    fn cache(tree: &Tree, entries: Entries) -> Cache {
+        Cache {
+            name: "test",
+            path: tree.path("cache"),
+            entries,
+        }
+    }
+
+    fn accessed_days_ago(path: &std::path::Path, days: u32) {
+        set_accessed(path, std::time::SystemTime::now() - DAY * days);
+    }
+
+    #[test]
+    fn whole_without_age() {
+        let tree = Tree::new();
+        tree.file("cache/src/index/serde-1.0.0/lib.rs", "");
+        let cache = cache(&tree, Entries::AtDepth(3));
+        assert_eq!(cache.prunable(None), [tree.path("cache")]);
+    }
+
+    #[test]
+    fn entries_at_depth_by_age() {
+        let tree = Tree::new();
+        let old = tree.file("cache/src/index/old-1.0.0/src/lib.rs", "");
+        let recent = tree.file("cache/src/index/recent-1.0.0/src/lib.rs", "");
+        // INFO: one recently read file keeps its whole entry.
+        let partly_old = tree.file("cache/src/index/partly-1.0.0/src/lib.rs", "");
+        let partly_recent = tree.file("cache/src/index/partly-1.0.0/Cargo.toml", "");
+        accessed_days_ago(&old, 100);
+        accessed_days_ago(&recent, 10);
+        accessed_days_ago(&partly_old, 100);
+        accessed_days_ago(&partly_recent, 10);
+
+        let cache = cache(&tree, Entries::AtDepth(3));
+        assert_eq!(
+            cache.prunable(Some(DAY * 90)),
+            [tree.path("cache/src/index/old-1.0.0")]
+        );
+        let mut prunable = cache.prunable(Some(DAY * 5));
+        prunable.sort();
+        assert_eq!(
+            prunable,
+            [
+                tree.path("cache/src/index/old-1.0.0"),
+                tree.path("cache/src/index/partly-1.0.0"),
+                tree.path("cache/src/index/recent-1.0.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_by_age() {
+        let tree = Tree::new();
+        let old = tree.file("cache/content-v2/sha512/ab/cdef", "");
+        let recent = tree.file("cache/content-v2/sha512/ab/0123", "");
+        accessed_days_ago(&old, 100);
+        accessed_days_ago(&recent, 10);
+
+        let cache = cache(&tree, Entries::Files);
+        assert_eq!(cache.prunable(Some(DAY * 90)), [old]);
+    }
+}
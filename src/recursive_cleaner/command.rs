@@ -0,0 +1,136 @@
+//! Running the cleanup commands of projects.
+
+/// Where `program` would be run from in `dir`: itself if it is a path, relative to `dir`, otherwise
+/// the first match in `$PATH`.
+pub fn find_program(program: &str, dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let is_executable = |path: &std::path::Path| {
+        path.metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    };
+    if program.contains('/') {
+        let path = dir.join(program);
+        return is_executable(&path).then_some(path);
+    }
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+/// The command as it would be typed, for humans.
+pub fn command_line(
+    program: impl AsRef<std::ffi::OsStr>,
+    args: impl IntoIterator<Item = impl AsRef<std::ffi::OsStr>>,
+) -> String {
+    let mut line = program.as_ref().to_string_lossy().into_owned();
+    args.into_iter().for_each(|arg| {
+        line.push(' ');
+        line.push_str(&arg.as_ref().to_string_lossy());
+    });
+    line
+}
+
+pub struct Output {
+    /// None when the command timed out.
+    pub status: Option<std::process::ExitStatus>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// How often a running command is checked on.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Run the command to completion, killing it if it runs for longer than `timeout`.
+pub fn output_with_timeout(
+    cmd: &mut std::process::Command,
+    timeout: std::time::Duration,
+) -> std::io::Result<Output> {
+    use std::io::Read;
+    use std::os::unix::process::CommandExt;
+
+    // INFO: in its own process group, so that what it spawned is killed with it on timeout and
+    // doesn't keep the pipes open.
+    let mut child = cmd
+        .process_group(0)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    // INFO: the pipes are drained while waiting, a command filling one would block forever.
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = vec![];
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = drain(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+    let started = std::time::Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if started.elapsed() >= timeout {
+            // SAFETY: kill has no memory safety requirements, the group is the child's own.
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests_output_with_timeout {
+    use super::*;
+
+    /// Whether the process still runs, zombies waiting for their parent to reap them aside.
+    fn is_running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+            stat.rsplit(')')
+                .next()
+                .is_some_and(|end| !end.starts_with(" Z"))
+        })
+    }
+
+    #[test]
+    fn output_of_quick_commands() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let output = output_with_timeout(&mut cmd, std::time::Duration::from_secs(10)).unwrap();
+        assert_eq!(output.status.and_then(|status| status.code()), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn timeout_kills_the_process_group() {
+        let mut cmd = std::process::Command::new("sh");
+        // INFO: the background sleep keeps stdout open, waiting on it would hang if it survived.
+        cmd.args(["-c", "sleep 30 & echo $!; wait"]);
+        let started = std::time::Instant::now();
+        let output = output_with_timeout(&mut cmd, std::time::Duration::from_millis(200)).unwrap();
+        assert!(output.status.is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        let pid = String::from_utf8(output.stdout).unwrap();
+        let pid = pid.trim();
+        assert!(!pid.is_empty());
+        // INFO: killed, but it may take a moment to go.
+        let gone = (0..50).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            !is_running(pid)
+        });
+        assert!(gone, "sleep {pid} survived the timeout");
+    }
+}
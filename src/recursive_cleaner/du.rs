@@ -0,0 +1,311 @@
+use super::{activity, find_project_files::ProjectLang, find_projects, plan, Found};
+use crate::disk_usage::{get_disk_usage_of, DiskUsage};
+use spinoff::spinners;
+use std::cmp::Reverse;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortBy {
+    /// Biggest projects first
+    #[default]
+    Size,
+    /// Most reclaimable space first
+    Reclaimable,
+    /// Least recently active first
+    Activity,
+    Path,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct Cli {
+    /// Column to sort by
+    #[arg(short, long, value_enum, default_value_t)]
+    pub sort: SortBy,
+
+    /// Only list the first N projects
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// Keep looking for projects inside of projects, for monorepos and workspaces
+    #[arg(long)]
+    pub nested: bool,
+
+    /// Leave git commits and fetches out of the last activity of projects
+    #[arg(long)]
+    pub no_git_activity: bool,
+
+    #[command(flatten)]
+    pub walk: crate::walk::WalkArgs,
+
+    #[arg(value_hint = clap::ValueHint::DirPath, required = true)]
+    pub base_dirs: Vec<String>,
+}
+
+/// A line of the listing.
+struct ProjectUsage {
+    path: std::path::PathBuf,
+    size: DiskUsage,
+    reclaimable: DiskUsage,
+    /// What a plan for each kind of the project alone would free.
+    reclaimable_by_lang: Vec<(ProjectLang, DiskUsage)>,
+    last_activity: Option<std::time::SystemTime>,
+}
+
+impl ProjectUsage {
+    /// Measure the project, leaving out what projects measured earlier already claimed, like a
+    /// workspace claims the target directory of its members.
+    fn measure(
+        project: Found,
+        git_activity: bool,
+        claimed: &mut std::collections::HashSet<std::path::PathBuf>,
+    ) -> Self {
+        let options = plan::Options::default();
+        let claimed_before = claimed.clone();
+        let mut plan = project.plan(&options);
+        plan.retain_unclaimed(claimed);
+        let reclaimable = plan.reclaimable();
+        let reclaimable_by_lang = match project.langs.as_slice() {
+            [lang] => vec![(*lang, reclaimable)],
+            langs => langs
+                .iter()
+                .map(|lang| {
+                    let mut plan = plan::Plan::for_project(&project.path, &[*lang], &options)
+                        .with_build_trees(&project.build_trees);
+                    plan.retain_unclaimed(&mut claimed_before.clone());
+                    (*lang, plan.reclaimable())
+                })
+                .collect(),
+        };
+        Self {
+            // INFO: out-of-source build trees count in the size of the project.
+            size: get_disk_usage_of(plan.measured_paths()),
+            last_activity: activity::last_activity(
+                &project.path,
+                plan.artefact_paths(),
+                git_activity,
+            ),
+            path: project.path,
+            reclaimable,
+            reclaimable_by_lang,
+        }
+    }
+
+    fn langs_display(&self) -> String {
+        self.reclaimable_by_lang
+            .iter()
+            .map(|(lang, usage)| {
+                format!(
+                    "{} {}",
+                    lang,
+                    humansize::format_size(usage.allocated, humansize::DECIMAL)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Cli {
+    pub fn run(self) {
+        self.walk.apply();
+        let mut spinner = crate::ui::Progress::new(
+            spinners::BouncingBar,
+            "Measuring projects",
+            spinoff::Streams::Stdout,
+        );
+        let mut claimed = std::collections::HashSet::new();
+        let mut projects: Vec<ProjectUsage> = self
+            .base_dirs
+            .iter()
+            .flat_map(|dir| find_projects(dir, self.nested))
+            .map(|project| ProjectUsage::measure(project, !self.no_git_activity, &mut claimed))
+            .collect();
+        spinner.clear();
+        sort(&mut projects, self.sort);
+        let n_found = projects.len();
+        if let Some(top) = self.top {
+            projects.truncate(top);
+        }
+        let _ = print_listing(&projects, n_found, &mut std::io::stdout());
+    }
+}
+
+fn sort(projects: &mut [ProjectUsage], by: SortBy) {
+    match by {
+        SortBy::Size => projects.sort_by_key(|project| Reverse(project.size.allocated)),
+        SortBy::Reclaimable => {
+            projects.sort_by_key(|project| Reverse(project.reclaimable.allocated))
+        }
+        SortBy::Activity => projects.sort_by_key(|project| project.last_activity),
+        SortBy::Path => projects.sort_by(|a, b| a.path.cmp(&b.path)),
+    }
+}
+
+fn print_listing(
+    projects: &[ProjectUsage],
+    n_found: usize,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    if projects.is_empty() {
+        return writeln!(out, "No projects found.");
+    }
+    let langs: Vec<String> = projects.iter().map(ProjectUsage::langs_display).collect();
+    let lang_width = langs
+        .iter()
+        .map(|langs| langs.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    writeln!(
+        out,
+        "{:>10}  {:>11}  {:<13}  {:<lang_width$}  PROJECT",
+        "SIZE", "RECLAIMABLE", "LAST ACTIVITY", "KINDS"
+    )?;
+    for (project, langs) in projects.iter().zip(&langs) {
+        writeln!(
+            out,
+            "{:>10}  {:>11}  {:<13}  {:<lang_width$}  {}",
+            humansize::format_size(project.size.allocated, humansize::DECIMAL),
+            humansize::format_size(project.reclaimable.allocated, humansize::DECIMAL),
+            activity::format_date(project.last_activity),
+            langs,
+            project.path.display()
+        )?;
+    }
+    // INFO: nested projects are in the size of the projects holding them.
+    let size: u64 = projects
+        .iter()
+        .filter(|project| {
+            !projects
+                .iter()
+                .any(|other| other.path != project.path && project.path.starts_with(&other.path))
+        })
+        .map(|project| project.size.allocated)
+        .sum();
+    let reclaimable: u64 = projects
+        .iter()
+        .map(|project| project.reclaimable.allocated)
+        .sum();
+    writeln!(
+        out,
+        "{} out of {} projects, {} in total, {} reclaimable.",
+        projects.len(),
+        n_found,
+        humansize::format_size(size, humansize::DECIMAL),
+        humansize::format_size(reclaimable, humansize::DECIMAL)
+    )
+}
+
+#[cfg(test)]
+mod tests_measure {
+    use super::{find_projects, print_listing, sort, ProjectLang, ProjectUsage, SortBy};
+    use crate::fixture::{set_modified, Tree};
+
+    fn measure(tree: &Tree) -> Vec<ProjectUsage> {
+        measure_nested(tree, false)
+    }
+
+    fn measure_nested(tree: &Tree, nested: bool) -> Vec<ProjectUsage> {
+        let mut claimed = std::collections::HashSet::new();
+        find_projects(tree.root.to_str().unwrap(), nested)
+            .into_iter()
+            .map(|project| ProjectUsage::measure(project, true, &mut claimed))
+            .collect()
+    }
+
+    fn paths(projects: &[ProjectUsage]) -> Vec<&std::path::Path> {
+        projects
+            .iter()
+            .map(|project| project.path.as_path())
+            .collect()
+    }
+
+    #[test]
+    fn reclaimable_by_kind() {
+        let tree = Tree::new();
+        tree.file("web/Cargo.toml", "[package]\nname = \"web\"\n");
+        tree.file("web/target/debug/web", &"x".repeat(20_000));
+        tree.file("web/package-lock.json", "{}");
+        tree.file("web/node_modules/left-pad/index.js", &"x".repeat(1_000));
+
+        let projects = measure(&tree);
+        assert_eq!(projects.len(), 1);
+        let by_lang = &projects[0].reclaimable_by_lang;
+        assert_eq!(
+            by_lang.iter().map(|(lang, _)| *lang).collect::<Vec<_>>(),
+            vec![ProjectLang::Rust, ProjectLang::Npm]
+        );
+        assert!(by_lang[0].1.apparent >= 20_000 && by_lang[1].1.apparent >= 1_000);
+        assert_eq!(
+            projects[0].reclaimable.apparent,
+            by_lang[0].1.apparent + by_lang[1].1.apparent
+        );
+        assert!(projects[0].size.apparent > projects[0].reclaimable.apparent);
+    }
+
+    #[test]
+    fn sorted_by_column() {
+        let tree = Tree::new();
+        tree.file("big/Cargo.toml", "[package]\nname = \"big\"\n");
+        tree.file("big/target/debug/big", &"x".repeat(100_000));
+        let old = tree.file("old/package-lock.json", "{}");
+        tree.file("old/node_modules/left-pad/index.js", &"x".repeat(1_000));
+        set_modified(
+            &old,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000),
+        );
+        let mut projects = measure(&tree);
+
+        sort(&mut projects, SortBy::Reclaimable);
+        assert_eq!(paths(&projects), [tree.path("big"), tree.path("old")]);
+        sort(&mut projects, SortBy::Activity);
+        assert_eq!(paths(&projects), [tree.path("old"), tree.path("big")]);
+        sort(&mut projects, SortBy::Path);
+        assert_eq!(paths(&projects), [tree.path("big"), tree.path("old")]);
+    }
+
+    #[test]
+    fn listing() {
+        let tree = Tree::new();
+        tree.file("web/Cargo.toml", "[package]\nname = \"web\"\n");
+        tree.file("web/package-lock.json", "{}");
+        let projects = measure(&tree);
+
+        let mut out = vec![];
+        print_listing(&projects, 3, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("KINDS              PROJECT"));
+        assert!(lines[1].contains("Rust 0 B, Npm 0 B"));
+        assert!(lines[1].ends_with(&tree.path("web").display().to_string()));
+        assert!(lines[2].starts_with("1 out of 3 projects"));
+    }
+
+    #[test]
+    fn nested_projects_count_once() {
+        let tree = Tree::new();
+        tree.file("ws/Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        tree.file("ws/crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+        tree.file("ws/target/debug/core", &"x".repeat(100_000));
+        let projects = measure_nested(&tree, true);
+
+        assert_eq!(
+            paths(&projects),
+            [tree.path("ws"), tree.path("ws/crates/core")]
+        );
+        assert!(projects[0].reclaimable.apparent >= 100_000);
+        // INFO: the target directory of the workspace, claimed by it.
+        assert_eq!(projects[1].reclaimable.apparent, 0);
+        assert!(projects[1].size.apparent < 100_000);
+
+        let mut out = vec![];
+        print_listing(&projects, 2, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let total = format!(
+            "2 out of 2 projects, {} in total, {} reclaimable.",
+            humansize::format_size(projects[0].size.allocated, humansize::DECIMAL),
+            humansize::format_size(projects[0].reclaimable.allocated, humansize::DECIMAL)
+        );
+        assert_eq!(out.lines().last(), Some(total.as_str()));
+    }
+}
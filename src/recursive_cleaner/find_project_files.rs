@@ -1,7 +1,9 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 
 // use crate::c_project::CustomDirEnt;
-type CustomState = (usize, Option<ProjectLang>);
+/// Kinds of project detected in a directory, held by its first marker file.
+type CustomState = (usize, Vec<ProjectLang>);
 pub type CustomDirEnt = jwalk::DirEntry<CustomState>;
 
 // TODO: sort folders first?
@@ -19,44 +21,202 @@ fn sort_predicate(
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ProjectLang {
+    /// Declared in the configuration file, index in its `projects`.
+    Custom(usize),
     CCpp,
+    CMake,
     Rust,
+    /// Yarn classic (v1).
     Yarn,
+    /// Yarn 2 and later, configured by a `.yarnrc.yml`.
+    YarnBerry,
     Npm,
+    Pnpm,
+    Bun,
+    Python,
+    Gradle,
+    Maven,
+}
+
+impl ProjectLang {
+    pub const BUILTIN: [ProjectLang; 11] = [
+        Self::CCpp,
+        Self::CMake,
+        Self::Rust,
+        Self::Yarn,
+        Self::YarnBerry,
+        Self::Npm,
+        Self::Pnpm,
+        Self::Bun,
+        Self::Python,
+        Self::Gradle,
+        Self::Maven,
+    ];
+
+    /// Name of the kind in the configuration file.
+    pub fn config_name(&self) -> &'static str {
+        match self {
+            Self::Custom(idx) => crate::config::get().projects[*idx].name.get_ref().as_str(),
+            Self::CCpp => "ccpp",
+            Self::CMake => "cmake",
+            Self::Rust => "rust",
+            Self::Yarn => "yarn",
+            Self::YarnBerry => "yarn-berry",
+            Self::Npm => "npm",
+            Self::Pnpm => "pnpm",
+            Self::Bun => "bun",
+            Self::Python => "python",
+            Self::Gradle => "gradle",
+            Self::Maven => "maven",
+        }
+    }
+}
+
+impl std::fmt::Display for ProjectLang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let to_write = match self {
+            Self::Custom(_) => self.config_name(),
+            Self::CCpp => "C/C++",
+            Self::CMake => "CMake",
+            Self::Rust => "Rust",
+            Self::Yarn => "Yarn",
+            Self::YarnBerry => "Yarn Berry",
+            Self::Npm => "Npm",
+            Self::Pnpm => "pnpm",
+            Self::Bun => "Bun",
+            Self::Python => "Python",
+            Self::Gradle => "Gradle",
+            Self::Maven => "Maven",
+        };
+        f.write_str(to_write)
+    }
 }
 
 struct ProjectMatcher {
     lang: ProjectLang,
-    file: &'static str,
+    file: Cow<'static, str>,
 }
 
-static MATCHERS: [ProjectMatcher; 6] = [
+static BUILTIN_MATCHERS: [ProjectMatcher; 19] = [
     ProjectMatcher {
         lang: ProjectLang::CCpp,
-        file: "Makefile",
+        file: Cow::Borrowed("Makefile"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::CMake,
+        file: Cow::Borrowed("CMakeLists.txt"),
     },
     ProjectMatcher {
         lang: ProjectLang::Rust,
-        file: "Cargo.toml",
+        file: Cow::Borrowed("Cargo.toml"),
     },
     ProjectMatcher {
         lang: ProjectLang::Yarn,
-        file: "yarn.lock",
+        file: Cow::Borrowed("yarn.lock"),
     },
     ProjectMatcher {
         lang: ProjectLang::Npm,
-        file: "package-lock.json",
+        file: Cow::Borrowed("package-lock.json"),
     },
     ProjectMatcher {
-        lang: ProjectLang::Npm,
-        file: "bun.lockb",
+        lang: ProjectLang::YarnBerry,
+        file: Cow::Borrowed(".yarnrc.yml"),
     },
     ProjectMatcher {
-        lang: ProjectLang::Npm,
-        file: "pnpm-lock.yaml",
+        lang: ProjectLang::Pnpm,
+        file: Cow::Borrowed("pnpm-lock.yaml"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Bun,
+        file: Cow::Borrowed("bun.lockb"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Bun,
+        file: Cow::Borrowed("bun.lock"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Python,
+        file: Cow::Borrowed("pyproject.toml"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Python,
+        file: Cow::Borrowed("setup.py"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Python,
+        file: Cow::Borrowed("requirements.txt"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Python,
+        file: Cow::Borrowed("Pipfile"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Python,
+        file: Cow::Borrowed("poetry.lock"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Gradle,
+        file: Cow::Borrowed("build.gradle"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Gradle,
+        file: Cow::Borrowed("build.gradle.kts"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Gradle,
+        file: Cow::Borrowed("settings.gradle"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Gradle,
+        file: Cow::Borrowed("settings.gradle.kts"),
+    },
+    ProjectMatcher {
+        lang: ProjectLang::Maven,
+        file: Cow::Borrowed("pom.xml"),
     },
 ];
 
+/// Built-in matchers with the configuration applied. Kinds declared in the configuration come
+/// first so that they can claim a marker file from a built-in kind.
+static MATCHERS: once_cell::sync::Lazy<Vec<ProjectMatcher>> = once_cell::sync::Lazy::new(|| {
+    let config = crate::config::get();
+    let mut matchers: Vec<ProjectMatcher> = config
+        .projects
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, project)| {
+            project.markers.iter().map(move |marker| ProjectMatcher {
+                lang: ProjectLang::Custom(idx),
+                file: Cow::Owned(marker.clone()),
+            })
+        })
+        .collect();
+    for lang in ProjectLang::BUILTIN {
+        matchers.extend(
+            markers_of(lang)
+                .into_iter()
+                .map(|file| ProjectMatcher { lang, file }),
+        );
+    }
+    matchers
+});
+
+/// Marker files of a built-in kind, as tweaked by the configuration. A disabled kind has none.
+pub fn markers_of(lang: ProjectLang) -> Vec<Cow<'static, str>> {
+    match crate::config::get().builtin_tweak(lang.config_name()) {
+        Some(tweak) if !tweak.enabled => vec![],
+        Some(crate::config::BuiltinTweak {
+            markers: Some(markers),
+            ..
+        }) => markers.iter().cloned().map(Cow::Owned).collect(),
+        _ => BUILTIN_MATCHERS
+            .iter()
+            .filter(|matcher| matcher.lang == lang)
+            .map(|matcher| matcher.file.clone())
+            .collect(),
+    }
+}
+
 fn get_project_lang(file_name: &std::ffi::OsStr) -> Option<ProjectLang> {
     let file_name = file_name.to_str()?;
     MATCHERS.iter().find_map(|matcher| {
@@ -68,26 +228,95 @@ fn get_project_lang(file_name: &std::ffi::OsStr) -> Option<ProjectLang> {
     })
 }
 
-pub fn iter(dir: &str) -> impl Iterator<Item = CustomDirEnt> {
-    let walk_dir = jwalk::WalkDirGeneric::<CustomState>::new(dir).process_read_dir(
-        |_depth, _path, _read_dir_state, children| {
+/// A directory containing this file is a CMake build tree.
+pub static CMAKE_CACHE: &str = "CMakeCache.txt";
+
+/// Directories that are never walked when looking for nested projects, as they hold artefacts or
+/// dependencies rather than projects of their own.
+static ARTEFACT_DIR_NAMES: [&str; 11] = [
+    ".git",
+    "target",
+    "node_modules",
+    "build",
+    "dist",
+    ".gradle",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".tox",
+    ".nox",
+];
+
+/// Walk `dir` looking for marker files. By default, the walk stops at the first directory that
+/// contains one; when `nested` is set, it goes on below project roots to find the projects they
+/// contain, leaving out their artefact directories.
+pub fn iter(dir: &str, nested: bool) -> impl Iterator<Item = CustomDirEnt> {
+    let walk_dir = crate::walk::walk_dir::<CustomState, _>(
+        std::path::Path::new(dir),
+        crate::walk::Scope::Search,
+        move |_depth, path, _read_dir_state, children| {
             // INFO: base usage for this callback
             // https://docs.rs/jwalk/0.8.1/jwalk/index.html#extended-example
 
             children.sort_by(sort_predicate);
 
-            let mut count: usize = 0;
-            children.iter_mut().for_each(|dir_ent_result| {
-                let _ = dir_ent_result.as_mut().map(|dir_entry| {
-                    dir_entry.client_state = get_project_lang(dir_entry.file_name());
-                    if dir_entry.client_state.is_some() {
-                        count += 1;
-                    }
+            let mut seen_langs: Vec<ProjectLang> = vec![];
+            let mut first_marker: Option<usize> = None;
+            children
+                .iter_mut()
+                .enumerate()
+                .for_each(|(idx, dir_ent_result)| {
+                    let _ = dir_ent_result.as_mut().map(|dir_entry| {
+                        dir_entry.client_state = vec![];
+                        if let Some(lang) = get_project_lang(dir_entry.file_name()) {
+                            first_marker.get_or_insert(idx);
+                            // INFO: several markers of the same kind (build.gradle and
+                            // settings.gradle...) only make one project.
+                            if !seen_langs.contains(&lang) {
+                                seen_langs.push(lang);
+                            }
+                        }
+                    });
                 });
-            });
+            // INFO: hidden files aren't walked, hidden markers (.yarnrc.yml...) are looked up
+            // directly. They only tell more about a directory that is already a project.
+            if first_marker.is_some() {
+                for matcher in MATCHERS
+                    .iter()
+                    .filter(|matcher| matcher.file.starts_with('.'))
+                {
+                    if !seen_langs.contains(&matcher.lang)
+                        && path.join(matcher.file.as_ref()).exists()
+                    {
+                        seen_langs.push(matcher.lang);
+                    }
+                }
+            }
+            // INFO: Berry projects have a yarn.lock too.
+            if seen_langs.contains(&ProjectLang::YarnBerry) {
+                seen_langs.retain(|lang| *lang != ProjectLang::Yarn);
+            }
+            // INFO: a directory can be a project of several kinds at once (Cargo.toml next
+            // to package-lock.json...), it is still a single project.
+            if let Some(Ok(dir_entry)) = first_marker.map(|idx| &mut children[idx]) {
+                dir_entry.client_state = seen_langs;
+            }
             // INFO: if at least one child indicates to us that we are in a
             // codebase, don't bother reading subdirectories.
-            if count != 0 {
+            // INFO: build trees hold no projects, only generated files. Their cache is still
+            // yielded, to find the project they were configured from.
+            let is_build_tree = path.join(CMAKE_CACHE).exists();
+            if first_marker.is_some() && nested && !is_build_tree {
+                children
+                    .iter_mut()
+                    .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+                    .filter(|dir_entry| {
+                        ARTEFACT_DIR_NAMES
+                            .iter()
+                            .any(|name| dir_entry.file_name() == *name)
+                    })
+                    .for_each(|dir_entry| dir_entry.read_children_path = None);
+            } else if first_marker.is_some() || is_build_tree {
                 children.iter_mut().for_each(|dir_ent_res| {
                     let _ = dir_ent_res
                         .as_mut()
@@ -1,36 +1,207 @@
-use std::io::Read;
-
-#[allow(dead_code)]
-pub enum IsElfError {
-    Io {
-        kind: std::io::ErrorKind,
-        when: &'static str,
-    },
-}
-pub fn is_elf<T: std::convert::AsRef<std::path::Path>>(file_path: T) -> Result<bool, IsElfError> {
-    let mut file = match std::fs::File::open(file_path) {
-        Ok(file) => file,
-        Err(err) => {
-            return Err(IsElfError::Io {
-                kind: err.kind(),
-                when: "opening file",
-            });
+use std::io::{Read, Seek};
+
+/// What an ELF file is for, from `e_type`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ElfType {
+    /// `ET_REL`, object files.
+    Relocatable,
+    /// `ET_EXEC`, or `ET_DYN` with an interpreter: position independent executables.
+    Executable,
+    /// `ET_DYN` without an interpreter.
+    SharedLibrary,
+    /// `ET_CORE`, core dumps.
+    Core,
+    Other(u16),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ElfHeader {
+    pub elf_type: ElfType,
+    /// `e_machine`, the architecture the file was built for.
+    pub machine: u16,
+}
+
+const MAGIC: [u8; 4] = [0x7F, b'E', b'L', b'F'];
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+const PT_INTERP: u32 = 3;
+/// Program headers looked at to tell executables from shared libraries, the interpreter comes
+/// first in practice.
+const MAX_PROGRAM_HEADERS: usize = 64;
+
+/// Fields of the header that are read, whatever the class.
+struct RawHeader {
+    little_endian: bool,
+    e_type: u16,
+    machine: u16,
+    phoff: u64,
+    phentsize: u16,
+    phnum: u16,
+}
+
+fn parse(bytes: &[u8]) -> Option<RawHeader> {
+    if bytes.len() < 52 || bytes[..4] != MAGIC {
+        return None;
+    }
+    let is_64_bits = match bytes[4] {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let little_endian = match bytes[5] {
+        1 => true,
+        2 => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let field = [bytes[offset], bytes[offset + 1]];
+        if little_endian {
+            u16::from_le_bytes(field)
+        } else {
+            u16::from_be_bytes(field)
         }
     };
-
-    let mut start_bytes: [u8; 4] = [0; 4];
-    match file.read_exact(&mut start_bytes) {
-        Ok(_) => {}
-        Err(err) => {
-            return Err(IsElfError::Io {
-                kind: err.kind(),
-                when: "reading on file",
-            });
+    let u32_at = |offset: usize| {
+        let field = bytes[offset..offset + 4].try_into().unwrap();
+        if little_endian {
+            u32::from_le_bytes(field)
+        } else {
+            u32::from_be_bytes(field)
+        }
+    };
+    let u64_at = |offset: usize| {
+        let field = bytes[offset..offset + 8].try_into().unwrap();
+        if little_endian {
+            u64::from_le_bytes(field)
+        } else {
+            u64::from_be_bytes(field)
         }
     };
-    if start_bytes.eq(&[0x7F, b'E', b'L', b'F']) {
-        Ok(true)
+    let (phoff, phentsize, phnum) = if is_64_bits {
+        if bytes.len() < 64 {
+            return None;
+        }
+        (u64_at(32), u16_at(54), u16_at(56))
     } else {
-        Ok(false)
+        (u32_at(28) as u64, u16_at(42), u16_at(44))
+    };
+    Some(RawHeader {
+        little_endian,
+        e_type: u16_at(16),
+        machine: u16_at(18),
+        phoff,
+        phentsize,
+        phnum,
+    })
+}
+
+/// Whether one of the program headers is `PT_INTERP`.
+fn has_interpreter(file: &mut std::fs::File, raw: &RawHeader) -> std::io::Result<bool> {
+    let entry_size = raw.phentsize as usize;
+    if entry_size < 4 {
+        return Ok(false);
+    }
+    let n_entries = (raw.phnum as usize).min(MAX_PROGRAM_HEADERS);
+    let mut table = vec![0; entry_size * n_entries];
+    file.seek(std::io::SeekFrom::Start(raw.phoff))?;
+    file.read_exact(&mut table)?;
+    Ok(table.chunks_exact(entry_size).any(|entry| {
+        let p_type = entry[..4].try_into().unwrap();
+        let p_type = if raw.little_endian {
+            u32::from_le_bytes(p_type)
+        } else {
+            u32::from_be_bytes(p_type)
+        };
+        p_type == PT_INTERP
+    }))
+}
+
+/// The header of the file, None if it isn't an ELF file.
+pub fn read_header<T: AsRef<std::path::Path>>(file_path: T) -> std::io::Result<Option<ElfHeader>> {
+    let mut file = std::fs::File::open(file_path)?;
+    let mut bytes = Vec::with_capacity(64);
+    file.by_ref().take(64).read_to_end(&mut bytes)?;
+    let Some(raw) = parse(&bytes) else {
+        return Ok(None);
+    };
+    let elf_type = match raw.e_type {
+        ET_REL => ElfType::Relocatable,
+        ET_EXEC => ElfType::Executable,
+        ET_DYN if has_interpreter(&mut file, &raw)? => ElfType::Executable,
+        ET_DYN => ElfType::SharedLibrary,
+        ET_CORE => ElfType::Core,
+        other => ElfType::Other(other),
+    };
+    Ok(Some(ElfHeader {
+        elf_type,
+        machine: raw.machine,
+    }))
+}
+
+/// Common name of an `e_machine`.
+pub fn machine_name(machine: u16) -> Option<&'static str> {
+    match machine {
+        3 => Some("x86"),
+        8 => Some("MIPS"),
+        20 => Some("PowerPC"),
+        21 => Some("PowerPC64"),
+        40 => Some("ARM"),
+        62 => Some("x86-64"),
+        183 => Some("AArch64"),
+        243 => Some("RISC-V"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use super::*;
+
+    fn header_64(e_type: u16, machine: u16) -> Vec<u8> {
+        let mut bytes = vec![0; 64];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4] = 2;
+        bytes[5] = 1;
+        bytes[16..18].copy_from_slice(&e_type.to_le_bytes());
+        bytes[18..20].copy_from_slice(&machine.to_le_bytes());
+        bytes[32..40].copy_from_slice(&64u64.to_le_bytes());
+        bytes[54..56].copy_from_slice(&56u16.to_le_bytes());
+        bytes[56..58].copy_from_slice(&9u16.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn reads_64_bits_little_endian() {
+        let raw = parse(&header_64(ET_CORE, 62)).unwrap();
+        assert_eq!(raw.e_type, ET_CORE);
+        assert_eq!(raw.machine, 62);
+        assert_eq!((raw.phoff, raw.phentsize, raw.phnum), (64, 56, 9));
+    }
+
+    #[test]
+    fn reads_32_bits_big_endian() {
+        let mut bytes = vec![0; 52];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4] = 1;
+        bytes[5] = 2;
+        bytes[16..18].copy_from_slice(&ET_REL.to_be_bytes());
+        bytes[18..20].copy_from_slice(&8u16.to_be_bytes());
+        bytes[28..32].copy_from_slice(&52u32.to_be_bytes());
+        let raw = parse(&bytes).unwrap();
+        assert!(!raw.little_endian);
+        assert_eq!((raw.e_type, raw.machine, raw.phoff), (ET_REL, 8, 52));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(
+            parse(b"#!/bin/sh\necho not an ELF file, but long enough for a header\n").is_none()
+        );
+        assert!(parse(&header_64(ET_EXEC, 62)[..40]).is_none());
+        let mut bad_class = header_64(ET_EXEC, 62);
+        bad_class[4] = 3;
+        assert!(parse(&bad_class).is_none());
     }
 }
@@ -0,0 +1,503 @@
+mod cmake;
+mod jvm;
+mod node;
+mod protect;
+mod python;
+mod rust;
+
+pub use cmake::configured_from;
+pub use rust::{cargo_home, TargetCleaning};
+
+use super::find_project_files::ProjectLang;
+use super::project;
+
+/// Directories that walks through a project never enter: the git database and installed
+/// dependencies hold neither sources nor artefacts of the project.
+pub(super) static NOT_WALKED: [&str; 2] = [".git", "node_modules"];
+
+/// A single step of cleaning a project.
+#[derive(Debug)]
+pub enum Action {
+    /// Remove a file or a whole directory.
+    Remove(std::path::PathBuf),
+    /// Run a cleanup command inside the project directory. `frees` lists the paths the command is
+    /// expected to get rid of, so that its effect can be estimated beforehand, and so that they
+    /// can be removed natively when the command can't do it.
+    Command {
+        program: String,
+        args: Vec<String>,
+        frees: Vec<std::path::PathBuf>,
+        /// Whether the program was found when planning.
+        installed: bool,
+    },
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Remove(path) => write!(f, "remove {}", path.display()),
+            Self::Command {
+                program,
+                args,
+                installed: false,
+                ..
+            } => write!(
+                f,
+                "remove natively what {} would ({} is not installed)",
+                super::command::command_line(program, args),
+                program
+            ),
+            Self::Command { program, args, .. } => {
+                write!(f, "run {}", super::command::command_line(program, args))
+            }
+        }
+    }
+}
+
+/// Choices from the command line that change what cleaning a project does.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Options {
+    pub rust_target: TargetCleaning,
+    /// Remove what C/C++ projects deliver (libraries, the binary their Makefile builds) too.
+    pub remove_deliverables: bool,
+}
+
+/// Everything that cleaning a project would do, computed without touching the disk. A directory
+/// can be a project of several kinds at once, their actions are then combined.
+pub struct Plan {
+    pub path: std::path::PathBuf,
+    pub langs: Vec<ProjectLang>,
+    pub actions: Vec<Action>,
+    /// Classification of the files, for C/C++ projects.
+    pub c_project: Option<project::Project>,
+}
+
+impl Plan {
+    pub fn for_project(path: &std::path::Path, langs: &[ProjectLang], options: &Options) -> Self {
+        let mut plan = Self {
+            path: path.to_owned(),
+            langs: langs.to_vec(),
+            actions: vec![],
+            c_project: None,
+        };
+        langs
+            .iter()
+            .for_each(|lang| plan.push_actions_for(*lang, options));
+        // INFO: kinds can share artefacts, like node_modules for yarn and npm.
+        let mut seen = std::collections::HashSet::new();
+        plan.actions.retain(|action| match action {
+            Action::Remove(path) => seen.insert(path.clone()),
+            Action::Command { .. } => true,
+        });
+        plan.protect();
+        plan
+    }
+
+    /// Add the out-of-source build trees of a CMake project, found while looking for projects.
+    pub fn with_build_trees(mut self, trees: &[std::path::PathBuf]) -> Self {
+        if self.langs.contains(&ProjectLang::CMake) {
+            for tree in trees {
+                if !self.artefact_paths().contains(tree) {
+                    let kept = self.protected(None, tree);
+                    self.actions.extend(kept.into_iter().map(Action::Remove));
+                }
+            }
+        }
+        self
+    }
+
+    /// Leave out of the removals what the project's `.candelaignore` protects, and directories
+    /// marked to be left alone. Directories holding protected paths are only partly removed.
+    fn protect(&mut self) {
+        let matcher = protect::matcher(&self.path);
+        let actions = std::mem::take(&mut self.actions);
+        for action in actions {
+            match action {
+                Action::Remove(path) => {
+                    let kept = self.protected(matcher.as_ref(), &path);
+                    self.actions.extend(kept.into_iter().map(Action::Remove));
+                }
+                action => self.actions.push(action),
+            }
+        }
+    }
+
+    /// What can be removed of `path`: the patterns of the project only apply inside of it, marked
+    /// directories anywhere. It is only carved when something in it is protected.
+    fn protected(
+        &self,
+        matcher: Option<&ignore::gitignore::Gitignore>,
+        path: &std::path::Path,
+    ) -> Vec<std::path::PathBuf> {
+        let is_inside = path.starts_with(&self.path);
+        // INFO: below the project, the path can be inside a marked directory.
+        let is_in_kept = is_inside
+            && path
+                .ancestors()
+                .skip(1)
+                .take_while(|ancestor| *ancestor != self.path)
+                .any(crate::walk::is_kept);
+        if is_in_kept {
+            return vec![];
+        }
+        let matcher = matcher.filter(|_| is_inside);
+        if matcher.is_none() && !crate::walk::holds_kept(path) {
+            return vec![path.to_owned()];
+        }
+        protect::carve(matcher, path)
+    }
+
+    fn push_actions_for(&mut self, lang: ProjectLang, options: &Options) {
+        let path = self.path.as_path();
+        let actions = &mut self.actions;
+        match lang {
+            ProjectLang::Custom(idx) => {
+                let project = &crate::config::get().projects[idx];
+                if let Some((program, args)) = project
+                    .command
+                    .as_ref()
+                    .and_then(|command| command.split_first())
+                {
+                    actions.push(Action::Command {
+                        program: program.clone(),
+                        args: args.to_vec(),
+                        frees: glob_matches(path, &project.fallback).collect(),
+                        installed: super::command::find_program(program, path).is_some(),
+                    });
+                }
+                actions.extend(glob_matches(path, &project.remove).map(Action::Remove));
+            }
+            ProjectLang::Yarn => {
+                actions.extend(
+                    node::yarn_classic_artefacts(path)
+                        .into_iter()
+                        .map(Action::Remove),
+                );
+            }
+            ProjectLang::YarnBerry => {
+                actions.extend(
+                    node::yarn_berry_artefacts(path)
+                        .into_iter()
+                        .map(Action::Remove),
+                );
+            }
+            ProjectLang::Npm => {
+                actions.extend(node::npm_artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::Pnpm => {
+                actions.extend(node::pnpm_artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::Bun => {
+                actions.extend(node::bun_artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::Rust => {
+                actions.extend(
+                    rust::artefacts(path, options.rust_target)
+                        .into_iter()
+                        .map(Action::Remove),
+                );
+            }
+            ProjectLang::Python => {
+                actions.extend(python::artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::Gradle => {
+                actions.extend(jvm::gradle_artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::Maven => {
+                actions.extend(jvm::maven_artefacts(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::CMake => {
+                actions.extend(cmake::build_trees(path).into_iter().map(Action::Remove));
+            }
+            ProjectLang::CCpp => {
+                let project =
+                    project::Project::from_c_project_path(path, options.remove_deliverables);
+                actions.extend(project.artefacts().map(Action::Remove));
+                self.c_project = Some(project);
+            }
+        }
+        if let Some(tweak) = crate::config::get().builtin_tweak(lang.config_name()) {
+            self.actions
+                .extend(glob_matches(&self.path, &tweak.remove).map(Action::Remove));
+        }
+    }
+
+    /// Kinds of the project, as a single string.
+    pub fn langs_display(&self) -> String {
+        self.langs
+            .iter()
+            .map(|lang| lang.to_string())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    /// C/C++ artefacts are found by guessing and CMake build trees are removed as a whole, both
+    /// are a bit much to delete without asking.
+    pub fn needs_confirmation(&self) -> bool {
+        self.langs
+            .iter()
+            .any(|lang| matches!(lang, ProjectLang::CCpp | ProjectLang::CMake))
+    }
+
+    /// Tells if the plan would actually free something, commands that have no known effect on the
+    /// project (like `yarn cache clean`) are not taken into account.
+    pub fn frees_something(&self) -> bool {
+        self.actions.iter().any(|action| match action {
+            Action::Remove(_) => true,
+            Action::Command { frees, .. } => !frees.is_empty(),
+        })
+    }
+
+    /// Drop the removals that an earlier plan already takes care of, by removing the path itself or
+    /// one of its parents, then claim the remaining ones. This happens with nested projects.
+    pub fn retain_unclaimed(
+        &mut self,
+        claimed: &mut std::collections::HashSet<std::path::PathBuf>,
+    ) {
+        self.actions.retain(|action| match action {
+            Action::Remove(path) => !path.ancestors().any(|ancestor| claimed.contains(ancestor)),
+            Action::Command { .. } => true,
+        });
+        claimed.extend(self.artefact_paths());
+    }
+
+    /// Paths that the plan would get rid of.
+    pub fn artefact_paths(&self) -> std::collections::HashSet<std::path::PathBuf> {
+        self.actions
+            .iter()
+            .flat_map(|action| match action {
+                Action::Remove(path) => std::slice::from_ref(path),
+                Action::Command { frees, .. } => frees.as_slice(),
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Space that executing the plan would free.
+    pub fn reclaimable(&self) -> crate::disk_usage::DiskUsage {
+        crate::disk_usage::get_disk_usage_of(self.artefact_paths())
+    }
+
+    /// Trees whose usage changes when executing the plan: the project, and artefacts that live
+    /// outside of it like shared Rust target directories.
+    pub fn measured_paths(&self) -> Vec<std::path::PathBuf> {
+        let mut paths: Vec<_> = self
+            .artefact_paths()
+            .into_iter()
+            .filter(|path| !path.starts_with(&self.path))
+            .collect();
+        paths.sort();
+        paths.insert(0, self.path.clone());
+        paths
+    }
+}
+
+/// Paths in the project matching any of the globs, patterns that aren't valid are skipped as they
+/// get reported when loading the configuration. Matches that resolve outside of the project are
+/// dropped, a symlink is only inside if the link itself is.
+fn glob_matches<'a>(
+    path: &'a std::path::Path,
+    patterns: &'a [toml::Spanned<String>],
+) -> impl Iterator<Item = std::path::PathBuf> + 'a {
+    let base = glob::Pattern::escape(&path.to_string_lossy());
+    let canonical_project = path.canonicalize().ok();
+    let is_inside = move |entry: &std::path::Path| {
+        let (Some(parent), Some(name)) = (entry.parent(), entry.file_name()) else {
+            return false;
+        };
+        let Some(canonical_project) = &canonical_project else {
+            return false;
+        };
+        parent.canonicalize().is_ok_and(|parent| {
+            let entry = parent.join(name);
+            entry != *canonical_project && entry.starts_with(canonical_project)
+        })
+    };
+    patterns
+        .iter()
+        .filter_map(move |pattern| glob::glob(&format!("{}/{}", base, pattern.get_ref())).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(move |entry| is_inside(entry))
+}
+
+/// How many actions of a plan are listed in the dry-run report before being summarised.
+const MAX_LISTED_ACTIONS: usize = 5;
+
+/// Print the plans, sorted by decreasing reclaimable size, as a table followed by the actions
+/// each plan would perform.
+pub fn print_report(sized: &[(crate::disk_usage::DiskUsage, Plan)]) {
+    if sized.is_empty() {
+        println!("Nothing to clean.");
+        return;
+    }
+    let lang_width = sized
+        .iter()
+        .map(|(_, plan)| plan.langs_display().len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    println!("{:>10}  {:<lang_width$}  PROJECT", "SIZE", "LANG");
+    for (size, plan) in sized {
+        println!(
+            "{:>10}  {:<lang_width$}  {}",
+            humansize::format_size(size.allocated, humansize::DECIMAL),
+            plan.langs_display(),
+            plan.path.display()
+        );
+        plan.actions
+            .iter()
+            .take(MAX_LISTED_ACTIONS)
+            .for_each(|action| println!("{:>20}{}", "", action));
+        if plan.actions.len() > MAX_LISTED_ACTIONS {
+            println!(
+                "{:>20}... and {} more",
+                "",
+                plan.actions.len() - MAX_LISTED_ACTIONS
+            );
+        }
+    }
+    let total: u64 = sized.iter().map(|(size, _)| size.allocated).sum();
+    println!(
+        "Would free {} across {} projects.",
+        humansize::format_size(total, humansize::DECIMAL),
+        sized.len()
+    );
+}
+
+#[cfg(test)]
+mod tests_action {
+    use super::Action;
+
+    fn command(args: &[&str], installed: bool) -> Action {
+        Action::Command {
+            program: "./clean.sh".to_owned(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            frees: vec![],
+            installed,
+        }
+    }
+
+    #[test]
+    fn commands_as_typed() {
+        assert_eq!(command(&[], true).to_string(), "run ./clean.sh");
+        assert_eq!(command(&["all"], true).to_string(), "run ./clean.sh all");
+        assert_eq!(
+            command(&[], false).to_string(),
+            "remove natively what ./clean.sh would (./clean.sh is not installed)"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_glob_matches {
+    use super::glob_matches;
+    use crate::config::unspanned;
+    use crate::fixture::Tree;
+
+    #[test]
+    fn stays_in_the_project() {
+        let tree = Tree::new();
+        tree.file("proj/out/a.bin", "");
+        tree.file("sibling/b.bin", "");
+        std::os::unix::fs::symlink(tree.path("sibling"), tree.path("proj/link")).unwrap();
+        let patterns =
+            ["out/*", "link", "link/*", "../sibling", "."].map(|p| unspanned(p.to_owned()));
+
+        let mut matches: Vec<_> = glob_matches(&tree.path("proj"), &patterns).collect();
+        matches.sort();
+        assert_eq!(
+            matches,
+            vec![tree.path("proj/link"), tree.path("proj/out/a.bin")]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_ccpp {
+    use super::{Options, Plan};
+    use crate::fixture::Tree;
+    use crate::recursive_cleaner::find_project_files::ProjectLang;
+
+    /// A small C project: sources, an object and the binary its Makefile builds.
+    fn c_project() -> Tree {
+        let tree = Tree::new();
+        tree.file("Makefile", "NAME = prog\nall: $(NAME)\n");
+        tree.file("main.c", "int main(void) { return 0; }\n");
+        tree.file("include/prog.h", "");
+        tree.file("main.o", "");
+        tree.file("prog", "");
+        tree
+    }
+
+    fn planned(tree: &Tree) -> std::collections::HashSet<std::path::PathBuf> {
+        Plan::for_project(&tree.root, &[ProjectLang::CCpp], &Options::default()).artefact_paths()
+    }
+
+    #[test]
+    fn debug_leftovers() {
+        let tree = c_project();
+        tree.file("vgcore.4242", "");
+        tree.file("prog.exe.stackdump", "");
+        tree.file("massif.out.4242", "");
+        tree.file("tests/callgrind.out.4242", "");
+        // INFO: a core dump is told by its ELF header, whatever its name.
+        let mut core = vec![0; 64];
+        core[..4].copy_from_slice(b"\x7fELF");
+        core[4] = 2;
+        core[5] = 1;
+        core[16..18].copy_from_slice(&4u16.to_le_bytes());
+        std::fs::write(tree.path("core"), core).unwrap();
+        // INFO: names alike that are no leftovers.
+        tree.file("vgcore.c", "");
+        tree.file("massif.out", "");
+
+        assert_eq!(
+            planned(&tree),
+            [
+                tree.path("main.o"),
+                tree.path("vgcore.4242"),
+                tree.path("prog.exe.stackdump"),
+                tree.path("massif.out.4242"),
+                tree.path("tests/callgrind.out.4242"),
+                tree.path("core"),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn coverage_and_tests() {
+        let tree = c_project();
+        tree.file("main.gcno", "");
+        tree.file("main.gcda", "");
+        tree.file("main.c.gcov", "");
+        tree.file("default.profraw", "");
+        tree.file("unit_tests", "");
+        tree.file("html/gcov.css", "");
+        tree.file("html/index.html", "");
+        tree.file(
+            "coverage/index.html",
+            "<meta name=\"generator\" content=\"gcovr\">",
+        );
+        tree.file("coverage/index.main.c.html", "");
+        // INFO: directories of the same name that are no reports.
+        tree.file("docs/html/index.html", "<h1>Manual</h1>");
+        tree.file("src/coverage/coverage.c", "");
+
+        assert_eq!(
+            planned(&tree),
+            [
+                tree.path("main.o"),
+                tree.path("main.gcno"),
+                tree.path("main.gcda"),
+                tree.path("main.c.gcov"),
+                tree.path("default.profraw"),
+                tree.path("unit_tests"),
+                tree.path("html"),
+                tree.path("coverage"),
+            ]
+            .into()
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use super::NOT_WALKED;
+
+/// Files and directories that CMake generates at the root of a build tree.
+static BUILD_TREE_MARKERS: [&str; 4] = [
+    "CMakeCache.txt",
+    "CMakeFiles",
+    "build.ninja",
+    "compile_commands.json",
+];
+
+/// Read `CMAKE_HOME_DIRECTORY` from the `CMakeCache.txt` of a build tree, this is the source
+/// directory that the tree was configured from.
+fn home_directory(build_tree: &std::path::Path) -> Option<std::path::PathBuf> {
+    let cache = std::fs::read_to_string(build_tree.join("CMakeCache.txt")).ok()?;
+    cache.lines().find_map(|line| {
+        let value = line.strip_prefix("CMAKE_HOME_DIRECTORY:")?;
+        let (_type, path) = value.split_once('=')?;
+        Some(std::path::PathBuf::from(path))
+    })
+}
+
+/// Tells if the build tree was configured from the project, wherever it is.
+pub fn configured_from(tree: &std::path::Path, project: &std::path::Path) -> bool {
+    let canonical = |path: &std::path::Path| path.canonicalize().ok();
+    home_directory(tree)
+        .is_some_and(|home| canonical(&home).is_some() && canonical(&home) == canonical(project))
+}
+
+fn has_build_tree_marker(path: &std::path::Path) -> bool {
+    BUILD_TREE_MARKERS
+        .iter()
+        .any(|marker| path.join(marker).exists())
+}
+
+/// A directory is a build tree of the project if its cache says it was configured from the
+/// project, or if it is an in-tree `build*` directory that looks like it was generated by CMake.
+fn is_build_tree_of(
+    tree: &std::path::Path,
+    project: &std::path::Path,
+    canonical_project: &std::path::Path,
+) -> bool {
+    if let Some(home) = home_directory(tree) {
+        return home.canonicalize().ok().as_deref() == Some(canonical_project);
+    }
+    tree.parent() == Some(project)
+        && tree
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("build"))
+        && has_build_tree_marker(tree)
+}
+
+/// List the build trees of a CMake project found inside of it. Those that live outside of it are
+/// found while looking for projects, see `Plan::with_build_trees`.
+pub fn build_trees(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let Ok(canonical_project) = path.canonicalize() else {
+        return vec![];
+    };
+    let walk_dir = crate::walk::walk_project(path, |_depth, _path, _read_dir_state, children| {
+        children
+            .iter_mut()
+            .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+            .filter(|dir_ent| dir_ent.file_type().is_dir())
+            .filter(|dir_ent| {
+                NOT_WALKED.iter().any(|name| dir_ent.file_name() == *name)
+                    || has_build_tree_marker(&dir_ent.path())
+            })
+            .for_each(|dir_ent| dir_ent.read_children_path = None);
+    })
+    .skip_hidden(false)
+    .sort(true);
+    walk_dir
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.depth > 0 && dir_ent.file_type().is_dir())
+        .map(|dir_ent| dir_ent.path())
+        .filter(|tree| is_build_tree_of(tree, path, &canonical_project))
+        .collect()
+}
@@ -0,0 +1,125 @@
+use super::NOT_WALKED;
+
+static GRADLE_BUILD_FILES: [&str; 4] = [
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
+];
+
+static MAVEN_BUILD_FILES: [&str; 1] = ["pom.xml"];
+
+/// Outputs of the modules, never walked when looking for modules on top of `NOT_WALKED`.
+static OUTPUTS: [&str; 3] = [".gradle", "build", "target"];
+
+/// Output directories of a Gradle build: `.gradle/` at the root and the `build/` directory of
+/// every module.
+pub fn gradle_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut found = module_outputs(path, &GRADLE_BUILD_FILES, "build");
+    let gradle_cache = path.join(".gradle");
+    if gradle_cache.is_dir() {
+        found.push(gradle_cache);
+    }
+    found
+}
+
+/// Output directories of a Maven build: the `target/` directory of every module.
+pub fn maven_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    module_outputs(path, &MAVEN_BUILD_FILES, "target")
+}
+
+/// Walk a multi-module project and list the `output` directory of every module, modules being the
+/// directories that contain one of the `build_files`.
+fn module_outputs(
+    path: &std::path::Path,
+    build_files: &'static [&'static str],
+    output: &'static str,
+) -> Vec<std::path::PathBuf> {
+    let walk_dir = crate::walk::walk_project(path, |_depth, _path, _read_dir_state, children| {
+        children
+            .iter_mut()
+            .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+            .filter(|dir_ent| dir_ent.file_type().is_dir())
+            .filter(|dir_ent| {
+                NOT_WALKED
+                    .iter()
+                    .chain(&OUTPUTS)
+                    .any(|name| dir_ent.file_name() == *name)
+            })
+            .for_each(|dir_ent| dir_ent.read_children_path = None);
+    })
+    .skip_hidden(false)
+    .sort(true);
+    walk_dir
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.file_type().is_dir() && dir_ent.file_name() == output)
+        .filter(|dir_ent| {
+            build_files
+                .iter()
+                .any(|build_file| dir_ent.parent_path().join(build_file).is_file())
+        })
+        .map(|dir_ent| dir_ent.path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests_module_outputs {
+    use super::{gradle_artefacts, maven_artefacts};
+    use crate::fixture::Tree;
+
+    fn sorted(mut found: Vec<std::path::PathBuf>) -> Vec<std::path::PathBuf> {
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn gradle_modules() {
+        let tree = Tree::new();
+        tree.file("settings.gradle.kts", "include(\"app\", \"lib\")\n");
+        tree.file(".gradle/8.5/checksums/checksums.lock", "");
+        tree.file("build/reports/problems.html", "");
+        tree.file("app/build.gradle.kts", "");
+        tree.file("app/build/classes/Main.class", "");
+        tree.file("lib/build.gradle", "");
+        tree.file("lib/build/libs/lib.jar", "");
+        // INFO: a build directory without a build file next to it is sources.
+        tree.file("docs/build/index.md", "");
+        // INFO: outputs are not walked, a module inside of one isn't.
+        tree.file("app/build/tmp/build.gradle", "");
+        tree.file("app/build/tmp/build/out", "");
+
+        assert_eq!(
+            sorted(gradle_artefacts(&tree.root)),
+            [
+                tree.path(".gradle"),
+                tree.path("app/build"),
+                tree.path("build"),
+                tree.path("lib/build"),
+            ]
+        );
+    }
+
+    #[test]
+    fn maven_modules() {
+        let tree = Tree::new();
+        tree.file("pom.xml", "<project/>");
+        tree.file("target/classes/App.class", "");
+        tree.file("core/pom.xml", "<project/>");
+        tree.file("core/target/core-1.0.jar", "");
+        tree.file("web/pom.xml", "<project/>");
+        tree.file("web/target/web-1.0.war", "");
+        tree.file("web/src/main/target/notes.txt", "");
+        tree.file("node_modules/pkg/pom.xml", "<project/>");
+        tree.file("node_modules/pkg/target/pkg.jar", "");
+
+        assert_eq!(
+            sorted(maven_artefacts(&tree.root)),
+            [
+                tree.path("core/target"),
+                tree.path("target"),
+                tree.path("web/target"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use super::NOT_WALKED;
+
+/// Files and directories that Yarn Berry generates when installing, the cache aside.
+static YARN_BERRY_INSTALL_STATE: [&str; 5] = [
+    ".yarn/install-state.gz",
+    ".yarn/build-state.yml",
+    ".yarn/unplugged",
+    ".pnp.cjs",
+    ".pnp.loader.mjs",
+];
+
+fn existing(path: std::path::PathBuf) -> Option<std::path::PathBuf> {
+    path.symlink_metadata().is_ok().then_some(path)
+}
+
+pub fn npm_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let dist = path.join("dist");
+    existing(path.join("node_modules"))
+        .into_iter()
+        .chain(dist.is_dir().then_some(dist))
+        .collect()
+}
+
+/// Only `node_modules`, `yarn cache clean` would empty the cache shared by every project of the
+/// machine.
+pub fn yarn_classic_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    existing(path.join("node_modules")).into_iter().collect()
+}
+
+/// What an install generates, and the project's `.yarn/cache` unless it is committed for
+/// zero-installs.
+pub fn yarn_berry_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut found: Vec<_> = YARN_BERRY_INSTALL_STATE
+        .iter()
+        .filter_map(|file| existing(path.join(file)))
+        .chain(existing(path.join("node_modules")))
+        .collect();
+    let gitignore = std::fs::read_to_string(path.join(".gitignore")).unwrap_or_default();
+    if ignores_yarn_cache(&gitignore) {
+        found.extend(existing(path.join(".yarn").join("cache")));
+    }
+    found
+}
+
+/// With zero-installs the cache is committed, it is only safe to remove when `.gitignore` leaves
+/// it out of the repository. Like git, the last matching line wins.
+fn ignores_yarn_cache(gitignore: &str) -> bool {
+    let mut ignored = false;
+    for line in gitignore.lines().map(str::trim) {
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line),
+        };
+        let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+        let covers_cache = match pattern {
+            ".yarn/cache" | ".yarn/cache/*" => true,
+            ".yarn" | ".yarn/*" => !negated,
+            _ => false,
+        };
+        if covers_cache {
+            ignored = !negated;
+        }
+    }
+    ignored
+}
+
+/// Every `node_modules` of the workspace, the root one holding the `.pnpm` virtual store, and the
+/// virtual store itself when `.npmrc` moves it elsewhere.
+pub fn pnpm_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let store = virtual_store_dir(path);
+    let not_walked_store = store.clone();
+    let walk_dir =
+        crate::walk::walk_project(path, move |_depth, _path, _read_dir_state, children| {
+            children
+                .iter_mut()
+                .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+                .filter(|dir_ent| dir_ent.file_type().is_dir())
+                .filter(|dir_ent| {
+                    NOT_WALKED.iter().any(|name| dir_ent.file_name() == *name)
+                        || not_walked_store.as_deref() == Some(dir_ent.path().as_path())
+                })
+                .for_each(|dir_ent| dir_ent.read_children_path = None);
+        })
+        .skip_hidden(false)
+        .sort(true);
+    let mut found: Vec<_> = walk_dir
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.file_type().is_dir() && dir_ent.file_name() == "node_modules")
+        .map(|dir_ent| dir_ent.path())
+        .collect();
+    if let Some(store) = store {
+        if !found.iter().any(|dir| store.starts_with(dir)) {
+            found.extend(existing(store));
+        }
+    }
+    found
+}
+
+fn virtual_store_dir(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    let npmrc = std::fs::read_to_string(path.join(".npmrc")).ok()?;
+    npmrc.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "virtual-store-dir").then(|| path.join(value.trim()))
+    })
+}
+
+/// Bun keeps its cache in the home directory, only `node_modules` belongs to the project.
+pub fn bun_artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    existing(path.join("node_modules")).into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests_ignores_yarn_cache {
+    use super::ignores_yarn_cache;
+
+    #[test]
+    fn recommended_gitignore() {
+        assert!(ignores_yarn_cache(
+            ".yarn/*\n!.yarn/patches\n!.yarn/releases\n"
+        ));
+    }
+
+    #[test]
+    fn zero_installs() {
+        assert!(!ignores_yarn_cache(".yarn/*\n!.yarn/cache\n"));
+    }
+
+    #[test]
+    fn not_mentioned() {
+        assert!(!ignores_yarn_cache("node_modules\n"));
+    }
+}
@@ -0,0 +1,132 @@
+/// File at the root of a project listing, like a `.gitignore`, the paths that must never be
+/// deleted.
+static IGNORE_FILE: &str = ".candelaignore";
+
+/// Patterns of the project's ignore file, if it has one.
+pub fn matcher(path: &std::path::Path) -> Option<ignore::gitignore::Gitignore> {
+    let ignore_file = path.join(IGNORE_FILE);
+    if !ignore_file.is_file() {
+        return None;
+    }
+    let mut builder = ignore::gitignore::GitignoreBuilder::new(path);
+    if let Some(err) = builder.add(&ignore_file) {
+        eprintln!("{}: {}", ignore_file.display(), err);
+    }
+    match builder.build() {
+        Ok(matcher) => Some(matcher),
+        Err(err) => {
+            eprintln!("{}: {}", ignore_file.display(), err);
+            None
+        }
+    }
+}
+
+/// Protected by the ignore file, or marked to be left alone.
+fn is_protected(matcher: Option<&ignore::gitignore::Gitignore>, path: &std::path::Path) -> bool {
+    crate::walk::is_kept(path)
+        || matcher.is_some_and(|matcher| {
+            matcher
+                .matched_path_or_any_parents(path, path.is_dir())
+                .is_ignore()
+        })
+}
+
+/// What can be removed of `path` without touching a protected path: nothing if it is protected,
+/// itself if nothing inside of it is, and otherwise the same for each of its children.
+pub fn carve(
+    matcher: Option<&ignore::gitignore::Gitignore>,
+    path: &std::path::Path,
+) -> Vec<std::path::PathBuf> {
+    if is_protected(matcher, path) {
+        return vec![];
+    }
+    let is_dir = path.symlink_metadata().is_ok_and(|meta| meta.is_dir());
+    if !is_dir {
+        return vec![path.to_owned()];
+    }
+    let Ok(read_dir) = std::fs::read_dir(path) else {
+        return vec![path.to_owned()];
+    };
+    let mut children: Vec<_> = read_dir
+        .filter_map(|dir_ent| dir_ent.ok())
+        .map(|dir_ent| dir_ent.path())
+        .collect();
+    children.sort();
+    let mut carved = vec![];
+    let mut untouched = true;
+    for child in &children {
+        let child_carved = carve(matcher, child);
+        untouched &= child_carved.len() == 1 && child_carved[0] == *child;
+        carved.extend(child_carved);
+    }
+    if untouched {
+        vec![path.to_owned()]
+    } else {
+        carved
+    }
+}
+
+#[cfg(test)]
+mod tests_carve {
+    use super::*;
+    use crate::fixture::Tree;
+
+    fn matcher(tree: &Tree, ignore_file: &str) -> ignore::gitignore::Gitignore {
+        tree.file(IGNORE_FILE, ignore_file);
+        super::matcher(&tree.root).unwrap()
+    }
+
+    #[test]
+    fn whole_when_nothing_is_protected() {
+        let tree = Tree::new();
+        tree.file("target/release/app", "");
+        let matcher = matcher(&tree, "other\n");
+        assert_eq!(
+            carve(Some(&matcher), &tree.path("target")),
+            [tree.path("target")]
+        );
+        assert_eq!(carve(None, &tree.path("target")), [tree.path("target")]);
+    }
+
+    #[test]
+    fn around_protected_paths() {
+        let tree = Tree::new();
+        tree.file("target/debug/app", "");
+        tree.file("target/release/app", "");
+        tree.file("target/release/build/out", "");
+        let matcher = matcher(&tree, "target/release/app\n");
+        assert_eq!(
+            carve(Some(&matcher), &tree.path("target")),
+            [tree.path("target/debug"), tree.path("target/release/build")]
+        );
+        assert_eq!(
+            carve(Some(&matcher), &tree.path("target/release/app")),
+            [] as [std::path::PathBuf; 0]
+        );
+    }
+
+    #[test]
+    fn negated_patterns() {
+        let tree = Tree::new();
+        tree.file("target/release/app", "");
+        tree.file("target/release/deps/libfoo.rlib", "");
+        tree.file("target/release/build/out", "");
+        let matcher = matcher(&tree, "target/release/*\n!target/release/build\n");
+        assert_eq!(
+            carve(Some(&matcher), &tree.path("target")),
+            [tree.path("target/release/build")]
+        );
+    }
+
+    #[test]
+    fn around_kept_directories() {
+        let tree = Tree::new();
+        tree.file("target/debug/app", "");
+        tree.file("target/release/.candela-keep", "");
+        tree.file("target/release/app", "");
+        assert_eq!(
+            carve(None, &tree.path("target")),
+            [tree.path("target/debug")]
+        );
+    }
+}
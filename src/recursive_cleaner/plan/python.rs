@@ -0,0 +1,154 @@
+use std::ffi::OsStr;
+
+use super::NOT_WALKED;
+
+/// Tool caches and build outputs that live at the root of a python project.
+static ROOT_ARTEFACTS: [&str; 8] = [
+    ".pytest_cache",
+    ".mypy_cache",
+    ".ruff_cache",
+    ".tox",
+    ".nox",
+    "build",
+    "dist",
+    ".eggs",
+];
+
+/// A directory is a virtualenv if it holds a `pyvenv.cfg`, whatever its name (`.venv`, `venv`,
+/// `env`...).
+fn is_virtualenv(path: &std::path::Path) -> bool {
+    path.join("pyvenv.cfg").is_file()
+}
+
+/// Directories found anywhere in the project that can be removed as a whole.
+fn is_nested_artefact(file_name: &OsStr) -> bool {
+    let Some(file_name) = file_name.to_str() else {
+        return false;
+    };
+    file_name == "__pycache__" || file_name.ends_with(".egg-info")
+}
+
+/// List everything that can be removed from a python project: tool caches, build outputs,
+/// `__pycache__` trees, `*.egg-info` and local virtualenvs.
+pub fn artefacts(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut found: Vec<std::path::PathBuf> = ROOT_ARTEFACTS
+        .iter()
+        .map(|name| path.join(name))
+        .filter(|path| path.is_dir())
+        .collect();
+
+    let walk_dir = crate::walk::walk_project(path, |depth, _path, _read_dir_state, children| {
+        children
+            .iter_mut()
+            .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+            .filter(|dir_ent| dir_ent.file_type().is_dir())
+            .for_each(|dir_ent| {
+                let file_name = dir_ent.file_name();
+                let is_root_artefact = depth == Some(0)
+                    && file_name
+                        .to_str()
+                        .is_some_and(|name| ROOT_ARTEFACTS.contains(&name));
+                // INFO: artefacts are removed as a whole, no need to look inside them.
+                if is_root_artefact
+                    || is_nested_artefact(file_name)
+                    || NOT_WALKED.iter().any(|name| file_name == *name)
+                    || is_virtualenv(&dir_ent.path())
+                {
+                    dir_ent.read_children_path = None;
+                }
+            });
+    })
+    .skip_hidden(false)
+    .sort(true);
+    walk_dir
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| dir_ent.depth > 0 && dir_ent.file_type().is_dir())
+        .filter(|dir_ent| {
+            is_nested_artefact(dir_ent.file_name())
+                || (dir_ent.depth == 1 && is_virtualenv(&dir_ent.path()))
+        })
+        .for_each(|dir_ent| found.push(dir_ent.path()));
+    found
+}
+
+#[cfg(test)]
+mod tests_artefacts {
+    use super::artefacts;
+    use crate::fixture::Tree;
+
+    fn sorted(tree: &Tree) -> Vec<std::path::PathBuf> {
+        let mut found = artefacts(&tree.root);
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn virtualenvs_under_any_name_at_the_root() {
+        let tree = Tree::new();
+        tree.file("pyproject.toml", "");
+        tree.file(".venv/pyvenv.cfg", "");
+        tree.file("env-3.12/pyvenv.cfg", "");
+        tree.file(
+            "env-3.12/lib/python3.12/site-packages/pkg/__pycache__/m.pyc",
+            "",
+        );
+        // INFO: deeper, it belongs to something else.
+        tree.file("tools/venv/pyvenv.cfg", "");
+        tree.file("venv-like/lib/site.py", "");
+
+        assert_eq!(sorted(&tree), [tree.path(".venv"), tree.path("env-3.12")]);
+    }
+
+    #[test]
+    fn nested_caches_and_egg_info() {
+        let tree = Tree::new();
+        tree.file("pyproject.toml", "");
+        tree.file("src/pkg/__pycache__/mod.cpython-312.pyc", "");
+        tree.file("src/pkg/sub/__pycache__/mod.cpython-312.pyc", "");
+        tree.file("src/pkg.egg-info/PKG-INFO", "");
+        tree.file("src/pkg/__pycache__/nested/__pycache__/x.pyc", "");
+
+        assert_eq!(
+            sorted(&tree),
+            [
+                tree.path("src/pkg/__pycache__"),
+                tree.path("src/pkg/sub/__pycache__"),
+                tree.path("src/pkg.egg-info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_walked() {
+        let tree = Tree::new();
+        tree.file("pyproject.toml", "");
+        tree.file("node_modules/pkg/__pycache__/x.pyc", "");
+        tree.file(".git/hooks/__pycache__/x.pyc", "");
+
+        assert_eq!(sorted(&tree), [] as [std::path::PathBuf; 0]);
+    }
+
+    #[test]
+    fn root_only_caches() {
+        let tree = Tree::new();
+        tree.file("pyproject.toml", "");
+        tree.file(".pytest_cache/v/cache/lastfailed", "");
+        tree.file(".mypy_cache/3.12/pkg.meta.json", "");
+        tree.file("dist/pkg-1.0.tar.gz", "");
+        tree.file("build/lib/pkg/__init__.py", "");
+        // INFO: sources of the project, not outputs.
+        tree.file("docs/build/conf.py", "");
+        tree.file("src/dist/__init__.py", "");
+
+        assert_eq!(
+            sorted(&tree),
+            [
+                tree.path(".mypy_cache"),
+                tree.path(".pytest_cache"),
+                tree.path("build"),
+                tree.path("dist"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,428 @@
+/// What to remove from the target directory of a Rust project.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TargetCleaning {
+    /// The whole target directory
+    #[default]
+    All,
+    /// Debug builds, release ones are kept
+    Debug,
+    /// Incremental caches and stale entries of `deps/`
+    Incremental,
+}
+
+/// Extensions of the files in `deps/` that are named after the library, with a `lib` prefix.
+static LIB_EXTENSIONS: [&str; 5] = ["rlib", "rmeta", "so", "a", "dylib"];
+
+/// Resolve the `..` in a path without touching the disk, so that it still compares equal to the
+/// paths found by walking.
+fn normalize(path: std::path::PathBuf) -> std::path::PathBuf {
+    let mut out = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir
+                if matches!(
+                    out.components().next_back(),
+                    Some(std::path::Component::Normal(_))
+                ) =>
+            {
+                out.pop();
+            }
+            std::path::Component::CurDir => {}
+            component => out.push(component),
+        }
+    }
+    out
+}
+
+fn read_manifest(dir: &std::path::Path) -> Option<toml::Table> {
+    std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Tells if the `[workspace]` table of the manifest in `root` lists `path` as a member.
+fn is_member(workspace: &toml::Value, root: &std::path::Path, path: &std::path::Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    if relative.as_os_str().is_empty() {
+        return true;
+    }
+    let paths = |key: &str| {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str())
+    };
+    if paths("exclude").any(|excluded| relative.starts_with(excluded)) {
+        return false;
+    }
+    paths("members").any(|member| {
+        glob::Pattern::new(member).is_ok_and(|pattern| pattern.matches_path(relative))
+    })
+}
+
+/// Root of the workspace that the crate at `path` belongs to, the crate itself when it isn't a
+/// member of any.
+fn workspace_root(path: &std::path::Path) -> std::path::PathBuf {
+    if let Some(manifest) = read_manifest(path) {
+        if manifest.contains_key("workspace") {
+            return path.to_owned();
+        }
+        let explicit = manifest
+            .get("package")
+            .and_then(|package| package.get("workspace"))
+            .and_then(|workspace| workspace.as_str());
+        if let Some(explicit) = explicit {
+            return normalize(path.join(explicit));
+        }
+    }
+    path.ancestors()
+        .skip(1)
+        .find(|ancestor| {
+            read_manifest(ancestor)
+                .and_then(|manifest| manifest.get("workspace").cloned())
+                .is_some_and(|workspace| is_member(&workspace, ancestor, path))
+        })
+        .unwrap_or(path)
+        .to_owned()
+}
+
+/// `$CARGO_HOME`, usually `~/.cargo`. None without either.
+pub fn cargo_home() -> Option<std::path::PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| Some(crate::xdg::home()?.join(".cargo")))
+}
+
+/// `build.target-dir` from the cargo configuration files that apply to `path`, the closest one
+/// first and `$CARGO_HOME/config.toml` last. Relative directories are relative to the parent of
+/// the `.cargo` directory holding the configuration.
+fn configured_target_dir(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    path.ancestors()
+        .map(|ancestor| ancestor.join(".cargo"))
+        .chain(cargo_home())
+        .find_map(|cargo_dir| {
+            ["config.toml", "config"].iter().find_map(|file| {
+                let config: toml::Table = std::fs::read_to_string(cargo_dir.join(file))
+                    .ok()?
+                    .parse()
+                    .ok()?;
+                let target_dir = config.get("build")?.get("target-dir")?.as_str()?;
+                Some(normalize(cargo_dir.parent()?.join(target_dir)))
+            })
+        })
+}
+
+/// Target directory of the crate at `path`, shared by every member of its workspace. Cargo's
+/// environment variables win over its configuration files.
+pub fn target_dir(path: &std::path::Path) -> std::path::PathBuf {
+    let root = workspace_root(path);
+    ["CARGO_TARGET_DIR", "CARGO_BUILD_TARGET_DIR"]
+        .iter()
+        .find_map(std::env::var_os)
+        .map(|dir| normalize(root.join(dir)))
+        .or_else(|| configured_target_dir(path))
+        .unwrap_or_else(|| root.join("target"))
+}
+
+/// Directories of the target directory holding the output of a profile: `debug/`, `release/`,
+/// and the same under a target triple.
+fn profile_dirs(target_dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let subdirs = |dir: &std::path::Path| -> Vec<std::path::PathBuf> {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|dir_ent| dir_ent.ok())
+            .filter(|dir_ent| {
+                dir_ent
+                    .file_type()
+                    .is_ok_and(|file_type| file_type.is_dir())
+            })
+            .map(|dir_ent| dir_ent.path())
+            .collect()
+    };
+    let is_profile = |dir: &std::path::Path| dir.join("deps").is_dir();
+    let mut found = vec![];
+    for dir in subdirs(target_dir) {
+        if is_profile(&dir) {
+            found.push(dir);
+        } else {
+            found.extend(subdirs(&dir).into_iter().filter(|dir| is_profile(dir)));
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Split the name of a file of `deps/` into the crate it was built for and the hash of the build,
+/// like `libserde-0123456789abcdef.rlib` into `serde` and `0123456789abcdef`.
+fn unit_of(file_name: &str) -> Option<(&str, &str)> {
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, Some(extension)),
+        None => (file_name, None),
+    };
+    let (name, hash) = stem.rsplit_once('-')?;
+    if hash.len() != 16 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let name = match extension {
+        Some(extension) if LIB_EXTENSIONS.contains(&extension) => {
+            name.strip_prefix("lib").unwrap_or(name)
+        }
+        _ => name,
+    };
+    Some((name, hash))
+}
+
+/// A build unit, as cargo fingerprints it in `.fingerprint/<name>-<hash>/` of a profile directory.
+#[derive(Debug)]
+struct Fingerprint {
+    /// Hash in the names of the files of the unit in `deps/`.
+    unit: String,
+    /// Hash of the fingerprint, as cargo writes it next to its JSON.
+    hash: String,
+    /// Fingerprint hashes of the units it was built from.
+    deps: Vec<String>,
+    /// What a rebuild of the same unit with other dependencies or another compiler shares.
+    config: String,
+    invoked: Option<std::time::SystemTime>,
+}
+
+/// Cargo writes the `u64` hashes of fingerprints as the hex of their little-endian bytes.
+fn fingerprint_hex(hash: u64) -> String {
+    hash.to_le_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn read_fingerprint(dir: &std::path::Path) -> Option<Fingerprint> {
+    let dir_name = dir.file_name()?.to_str()?;
+    let (_name, unit) = dir_name.rsplit_once('-')?;
+    // INFO: `lib-syn`, `bin-candela`, `test-bin-candela`..., next to `<file>.json`.
+    let (file, hash) = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter_map(|dir_ent| dir_ent.file_name().into_string().ok())
+        .filter(|file| !file.starts_with("dep-") && !file.contains('.'))
+        .find_map(|file| {
+            let hash = std::fs::read_to_string(dir.join(&file)).ok()?;
+            (hash.len() == 16 && hash.bytes().all(|byte| byte.is_ascii_hexdigit()))
+                .then_some((file, hash))
+        })?;
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join(format!("{file}.json"))).ok()?)
+            .ok()?;
+    let deps = json
+        .get("deps")?
+        .as_array()?
+        .iter()
+        .filter_map(|dep| dep.get(3)?.as_u64().map(fingerprint_hex))
+        .collect();
+    let config = ["features", "target", "profile", "rustflags", "compile_kind"]
+        .iter()
+        .map(|key| {
+            json.get(key)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        })
+        .fold(file, |config, value| config + " " + &value);
+    let invoked = std::fs::metadata(dir.join("invoked.timestamp"))
+        .and_then(|meta| meta.modified())
+        .ok();
+    Some(Fingerprint {
+        unit: unit.to_owned(),
+        hash,
+        deps,
+        config,
+        invoked,
+    })
+}
+
+/// Units that the latest builds use: the newest build of each unit that nothing was built from
+/// (binaries, tests, libraries of the workspace...), and everything they were built from.
+fn live_units(fingerprints: &[Fingerprint]) -> std::collections::HashSet<&str> {
+    let used: std::collections::HashSet<&str> = fingerprints
+        .iter()
+        .flat_map(|fingerprint| fingerprint.deps.iter().map(String::as_str))
+        .collect();
+    let mut newest: std::collections::HashMap<&str, &Fingerprint> = Default::default();
+    for fingerprint in fingerprints
+        .iter()
+        .filter(|fingerprint| !used.contains(fingerprint.hash.as_str()))
+    {
+        let entry = newest.entry(&fingerprint.config).or_insert(fingerprint);
+        if fingerprint.invoked > entry.invoked {
+            *entry = fingerprint;
+        }
+    }
+    let by_hash: std::collections::HashMap<&str, &Fingerprint> = fingerprints
+        .iter()
+        .map(|fingerprint| (fingerprint.hash.as_str(), fingerprint))
+        .collect();
+    let mut live = std::collections::HashSet::new();
+    let mut to_visit: Vec<&Fingerprint> = newest.into_values().collect();
+    while let Some(fingerprint) = to_visit.pop() {
+        if live.insert(fingerprint.unit.as_str()) {
+            to_visit.extend(
+                fingerprint
+                    .deps
+                    .iter()
+                    .filter_map(|dep| by_hash.get(dep.as_str())),
+            );
+        }
+    }
+    live
+}
+
+/// Files of `deps/` that no current build uses anymore, from what cargo fingerprinted in the
+/// profile directory. Several versions of a crate can be in use at once, and units that cargo
+/// doesn't know of are kept.
+fn stale_deps(profile: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let fingerprints: Vec<Fingerprint> = std::fs::read_dir(profile.join(".fingerprint"))
+        .into_iter()
+        .flatten()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter_map(|dir_ent| read_fingerprint(&dir_ent.path()))
+        .collect();
+    let live = live_units(&fingerprints);
+    let known: std::collections::HashSet<&str> = fingerprints
+        .iter()
+        .map(|fingerprint| fingerprint.unit.as_str())
+        .collect();
+    let mut stale: Vec<std::path::PathBuf> = std::fs::read_dir(profile.join("deps"))
+        .into_iter()
+        .flatten()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .filter(|dir_ent| {
+            let file_name = dir_ent.file_name();
+            file_name
+                .to_str()
+                .and_then(unit_of)
+                .is_some_and(|(_name, hash)| known.contains(hash) && !live.contains(hash))
+        })
+        .map(|dir_ent| dir_ent.path())
+        .collect();
+    stale.sort();
+    stale
+}
+
+/// Paths to remove to clean the crate at `path`, in its workspace's target directory.
+pub fn artefacts(path: &std::path::Path, cleaning: TargetCleaning) -> Vec<std::path::PathBuf> {
+    let target_dir = target_dir(path);
+    if !target_dir.is_dir() {
+        return vec![];
+    }
+    match cleaning {
+        TargetCleaning::All => vec![target_dir],
+        TargetCleaning::Debug => profile_dirs(&target_dir)
+            .into_iter()
+            .filter(|dir| dir.file_name().is_some_and(|name| name == "debug"))
+            .collect(),
+        TargetCleaning::Incremental => profile_dirs(&target_dir)
+            .into_iter()
+            .flat_map(|dir| {
+                let incremental = dir.join("incremental");
+                incremental
+                    .is_dir()
+                    .then_some(incremental)
+                    .into_iter()
+                    .chain(stale_deps(&dir))
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests_unit_of {
+    use super::unit_of;
+
+    #[test]
+    fn library() {
+        assert_eq!(
+            unit_of("libserde-0123456789abcdef.rlib"),
+            Some(("serde", "0123456789abcdef"))
+        );
+    }
+
+    #[test]
+    fn dep_info_and_binary() {
+        assert_eq!(
+            unit_of("serde-0123456789abcdef.d"),
+            Some(("serde", "0123456789abcdef"))
+        );
+        assert_eq!(
+            unit_of("candela-0123456789abcdef"),
+            Some(("candela", "0123456789abcdef"))
+        );
+    }
+
+    #[test]
+    fn not_a_build_output() {
+        assert_eq!(unit_of("build-script-build"), None);
+    }
+}
+
+#[cfg(test)]
+mod tests_stale_deps {
+    use super::{fingerprint_hex, stale_deps};
+    use crate::fixture::{set_modified, Tree};
+
+    /// Fingerprint a unit built from the units with the `deps` fingerprints.
+    fn fingerprint(tree: &Tree, unit: &str, file: &str, hash: u64, deps: &[u64], invoked: u64) {
+        let dir = format!("debug/.fingerprint/{unit}");
+        let deps: Vec<_> = deps
+            .iter()
+            .map(|dep| serde_json::json!([0, "dep", false, dep]))
+            .collect();
+        let json = serde_json::json!({
+            "features": "[]",
+            "target": 1,
+            "profile": 2,
+            "deps": deps,
+        });
+        tree.file(&format!("{dir}/{file}"), &fingerprint_hex(hash));
+        tree.file(&format!("{dir}/{file}.json"), &json.to_string());
+        let timestamp = tree.file(&format!("{dir}/invoked.timestamp"), "");
+        set_modified(
+            &timestamp,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(invoked),
+        );
+    }
+
+    #[test]
+    fn keeps_every_version_in_use() {
+        let tree = Tree::new();
+        fingerprint(&tree, "app-aaaaaaaaaaaaaaaa", "bin-app", 10, &[1, 2], 2000);
+        fingerprint(&tree, "syn-1111111111111111", "lib-syn", 1, &[], 1900);
+        fingerprint(&tree, "syn-2222222222222222", "lib-syn", 2, &[], 1900);
+        // INFO: an older build of the binary, before syn was upgraded.
+        fingerprint(&tree, "app-bbbbbbbbbbbbbbbb", "bin-app", 11, &[3], 1000);
+        fingerprint(&tree, "syn-3333333333333333", "lib-syn", 3, &[], 900);
+        for file in [
+            "app-aaaaaaaaaaaaaaaa",
+            "app-bbbbbbbbbbbbbbbb",
+            "libsyn-1111111111111111.rlib",
+            "libsyn-2222222222222222.rlib",
+            "libsyn-3333333333333333.rlib",
+            "syn-3333333333333333.d",
+            // INFO: unknown to cargo's fingerprints, kept.
+            "libquote-4444444444444444.rlib",
+        ] {
+            tree.file(&format!("debug/deps/{file}"), "");
+        }
+
+        assert_eq!(
+            stale_deps(&tree.path("debug")),
+            vec![
+                tree.path("debug/deps/app-bbbbbbbbbbbbbbbb"),
+                tree.path("debug/deps/libsyn-3333333333333333.rlib"),
+                tree.path("debug/deps/syn-3333333333333333.d"),
+            ]
+        );
+    }
+}
@@ -1,48 +1,68 @@
+use super::is_elf::{machine_name, read_header};
 use std::cell::RefCell;
 
 mod ccpp;
+mod makefile;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 pub enum FileKind {
     Source,
     Deliverable,
     Temporary,
-    OtherElf,
+    /// Executables, ELF or PE, that no rule matched.
+    Executable,
+    /// Core dumps and outputs of debuggers and profilers.
+    DebugLeftover,
+    /// Coverage data and reports, test binaries.
+    Coverage,
     #[default]
     Other,
 }
 
+pub fn effective_ccpp_rules() -> crate::config::CCppRules {
+    ccpp::effective_rules()
+}
+
 pub struct Project {
     pub path: std::path::PathBuf,
     pub files: Vec<ccpp::CustomDirEnt>,
     pub artefacts_sizes: std::cell::RefCell<Option<u64>>,
+    /// Whether deliverables go with the other artefacts.
+    pub remove_deliverables: bool,
 }
 
 impl Project {
-    pub fn from_c_project_path(base_path: &std::path::Path) -> Self {
+    pub fn from_c_project_path(base_path: &std::path::Path, remove_deliverables: bool) -> Self {
         let files: Vec<ccpp::CustomDirEnt> = ccpp::id_temporary_files(base_path)
-            .filter_map(|file| match file {
-                Ok(file) => Some(file),
-                _ => None,
-            })
+            .filter_map(|file| file.ok())
             .collect();
 
         Project {
             path: base_path.to_owned(),
             files,
             artefacts_sizes: RefCell::new(None),
+            remove_deliverables,
         }
     }
 
-    fn compute_artefacts_sizes(&self) {
-        let sum: u64 = self
-            .files
+    /// Paths of the files that can be removed to clean the project.
+    pub fn artefacts(&self) -> impl Iterator<Item = std::path::PathBuf> + '_ {
+        self.files
             .iter()
-            .filter(|entry| matches!(entry.client_state, FileKind::Temporary | FileKind::OtherElf))
+            .filter(|entry| match entry.client_state {
+                FileKind::Temporary
+                | FileKind::Executable
+                | FileKind::DebugLeftover
+                | FileKind::Coverage => true,
+                FileKind::Deliverable => self.remove_deliverables,
+                FileKind::Source | FileKind::Other => false,
+            })
             .map(|file| file.path())
-            .filter_map(|path| path.metadata().ok())
-            .map(|meta| meta.len())
-            .sum();
+    }
+
+    fn compute_artefacts_sizes(&self) {
+        // INFO: coverage reports are directories.
+        let sum = crate::disk_usage::get_disk_usage_of(self.artefacts()).apparent;
         *self.artefacts_sizes.borrow_mut() = Some(sum);
     }
 
@@ -56,7 +76,20 @@ impl Project {
         }
     }
 
-    pub fn pretty_print(&self) {
+    /// How many files of this kind there are, and their size.
+    fn count_and_size(&self, kind: FileKind) -> (usize, u64) {
+        let paths: Vec<_> = self
+            .files
+            .iter()
+            .filter(|file| file.client_state == kind)
+            .map(|file| file.path())
+            .collect();
+        let size = crate::disk_usage::get_disk_usage_of(&paths).apparent;
+        (paths.len(), size)
+    }
+
+    /// Describe the project and what would be removed from it.
+    pub fn pretty_print(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
         let n_temporary = self
             .files
             .iter()
@@ -67,19 +100,51 @@ impl Project {
             .iter()
             .filter(|file| file.client_state == FileKind::Deliverable)
             .count();
-        let n_elf = self
+        let executables: Vec<_> = self
             .files
             .iter()
-            .filter(|file| file.client_state == FileKind::OtherElf)
-            .count();
+            .filter(|file| file.client_state == FileKind::Executable)
+            .collect();
+        let mut machines: Vec<&str> = executables
+            .iter()
+            .filter_map(|file| read_header(file.path()).ok().flatten())
+            .filter_map(|header| machine_name(header.machine))
+            .collect();
+        machines.sort();
+        machines.dedup();
+        let (n_debug_leftovers, debug_leftovers_size) =
+            self.count_and_size(FileKind::DebugLeftover);
+        let (n_coverage, coverage_size) = self.count_and_size(FileKind::Coverage);
 
-        println!("- Project {}", self.path.display());
-        println!("    - {} temporary files", n_temporary);
-        println!("    - {} deliverable files", n_deliverable);
-        println!("    - {} ELF files", n_elf);
-        println!(
+        writeln!(out, "- Project {}", self.path.display())?;
+        writeln!(out, "    - {} temporary files", n_temporary)?;
+        writeln!(out, "    - {} deliverable files", n_deliverable)?;
+        if machines.is_empty() {
+            writeln!(out, "    - {} executables", executables.len())?;
+        } else {
+            writeln!(
+                out,
+                "    - {} executables ({})",
+                executables.len(),
+                machines.join(", ")
+            )?;
+        }
+        writeln!(
+            out,
+            "    - {} debug leftovers ({})",
+            n_debug_leftovers,
+            size::Size::from_bytes(debug_leftovers_size)
+        )?;
+        writeln!(
+            out,
+            "    - {} coverage and test artefacts ({})",
+            n_coverage,
+            size::Size::from_bytes(coverage_size)
+        )?;
+        writeln!(
+            out,
             "    - size of artefacts {} ",
             size::Size::from_bytes(self.get_or_compute_artefact_sizes())
-        );
+        )
     }
 }
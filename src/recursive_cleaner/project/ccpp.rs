@@ -1,4 +1,5 @@
 use regex::Regex;
+use std::collections::HashSet;
 
 use super::FileKind;
 
@@ -9,7 +10,9 @@ impl std::fmt::Display for FileKind {
             Self::Deliverable => "Deliverable",
             Self::Temporary => "Temporary",
             Self::Other => "Other",
-            Self::OtherElf => "Other Elf",
+            Self::Executable => "Executable",
+            Self::DebugLeftover => "Debug leftover",
+            Self::Coverage => "Coverage or test",
         };
         f.write_str(to_write)
     }
@@ -32,24 +35,69 @@ fn is_to_retain(dir_entry: &CustomDirEnt) -> bool {
     dir_entry.file_type().is_dir() || dir_entry.client_state != FileKind::Other
 }
 
-static MATCHERS: once_cell::sync::Lazy<[FileMatcher; 3]> = once_cell::sync::Lazy::new(|| {
-    [
-        FileMatcher {
-            regex: Regex::new(r".+\.(a|out|so)$").expect("the regex to be valid"),
-            kind: FileKind::Deliverable,
-        },
-        FileMatcher {
-            regex: Regex::new(r".+\.(o|gch)$").expect("the regex to be valid"),
-            kind: FileKind::Temporary,
-        },
-        FileMatcher {
-            regex: Regex::new(r".+\.(c|h|cpp|hpp|cc|hh)$").expect("the regex to be valid"),
-            kind: FileKind::Source,
-        },
+static BUILTIN_RULES: [(&str, FileKind); 9] = [
+    // INFO: core dumps themselves are told by their ELF header, whatever their name.
+    (r"^vgcore\.\d+$", FileKind::DebugLeftover),
+    (r".+\.stackdump$", FileKind::DebugLeftover),
+    (r"^massif\.out\..+$", FileKind::DebugLeftover),
+    (r"^callgrind\.out\..+$", FileKind::DebugLeftover),
+    (r".+\.(gcno|gcda|gcov|profraw)$", FileKind::Coverage),
+    // INFO: the binary of Criterion tests, by convention.
+    (r"^unit_tests$", FileKind::Coverage),
+    (r".+\.(a|out|so)$", FileKind::Deliverable),
+    (r".+\.(o|gch)$", FileKind::Temporary),
+    (r".+\.(c|h|cpp|hpp|cc|hh)$", FileKind::Source),
+];
+
+/// Rules from the configuration, then built-in ones.
+fn rules() -> impl Iterator<Item = (&'static str, FileKind)> {
+    let config = &crate::config::get().ccpp;
+    let configured = [
+        (&config.deliverable, FileKind::Deliverable),
+        (&config.temporary, FileKind::Temporary),
+        (&config.source, FileKind::Source),
     ]
+    .into_iter()
+    .flat_map(|(regexes, kind)| {
+        regexes
+            .iter()
+            .map(move |regex| (regex.get_ref().as_str(), kind))
+    });
+    configured.chain(BUILTIN_RULES.iter().copied())
+}
+
+static MATCHERS: once_cell::sync::Lazy<Vec<FileMatcher>> = once_cell::sync::Lazy::new(|| {
+    // INFO: invalid regexes from the configuration are reported when it is loaded.
+    rules()
+        .filter_map(|(regex, kind)| {
+            Some(FileMatcher {
+                regex: Regex::new(regex).ok()?,
+                kind,
+            })
+        })
+        .collect()
 });
 
-fn tag_file(dir_entry: &mut CustomDirEnt) {
+pub fn effective_rules() -> crate::config::CCppRules {
+    let mut out = crate::config::CCppRules::default();
+    rules().for_each(|(regex, kind)| {
+        let regex = crate::config::unspanned(regex.to_owned());
+        match kind {
+            FileKind::Deliverable => out.deliverable.push(regex),
+            FileKind::Temporary => out.temporary.push(regex),
+            FileKind::Source => out.source.push(regex),
+            // INFO: debug leftovers can't be configured.
+            _ => {}
+        }
+    });
+    out
+}
+
+fn tag_file(dir_entry: &mut CustomDirEnt, deliverables: &HashSet<std::path::PathBuf>) {
+    if deliverables.contains(&dir_entry.path()) {
+        dir_entry.client_state = FileKind::Deliverable;
+        return;
+    }
     let Some(file_name) = dir_entry.file_name().to_str() else {
         return;
     };
@@ -61,38 +109,92 @@ fn tag_file(dir_entry: &mut CustomDirEnt) {
         }
     }) {
         Some(kind) => dir_entry.client_state = kind,
-        _ => {
-            if let Ok(Some(kind)) = infer::get_from_path(dir_entry.path()) {
-                if matches!(kind.extension(), "elf" | "exe") {
-                    dir_entry.client_state = FileKind::OtherElf;
-                }
-            }
-        }
+        _ => dir_entry.client_state = tag_binary(&dir_entry.path()),
     };
 }
 
+/// Kind of a file that no rule matched, from its ELF header: object files are temporary, core
+/// dumps debug leftovers, shared libraries are deliverables and executables are build outputs.
+fn tag_binary(path: &std::path::Path) -> FileKind {
+    use crate::recursive_cleaner::is_elf::{read_header, ElfType};
+
+    match read_header(path) {
+        Ok(Some(header)) => match header.elf_type {
+            ElfType::Relocatable => FileKind::Temporary,
+            ElfType::Core => FileKind::DebugLeftover,
+            ElfType::SharedLibrary => FileKind::Deliverable,
+            ElfType::Executable => FileKind::Executable,
+            ElfType::Other(_) => FileKind::Other,
+        },
+        // INFO: PE executables of cross-compiled projects.
+        Ok(None)
+            if infer::get_from_path(path)
+                .is_ok_and(|kind| kind.is_some_and(|kind| kind.extension() == "exe")) =>
+        {
+            FileKind::Executable
+        }
+        _ => FileKind::Other,
+    }
+}
+
+/// Names of the directories that coverage reports are usually generated in.
+static COVERAGE_REPORT_DIRS: [&str; 2] = ["html", "coverage"];
+
+/// Whether the directory is an HTML report of lcov (`genhtml`) or gcovr.
+fn is_coverage_report(path: &std::path::Path) -> bool {
+    path.join("gcov.css").is_file()
+        || std::fs::read_to_string(path.join("index.html"))
+            .is_ok_and(|index| index.contains("gcovr") || index.contains("LCOV"))
+}
+
+/// Tag coverage reports among the directories, they are removed as a whole so there is no need
+/// to walk them.
+fn tag_dir(dir_entry: &mut CustomDirEnt) {
+    let is_report_dir = COVERAGE_REPORT_DIRS
+        .iter()
+        .any(|name| dir_entry.file_name() == *name);
+    if is_report_dir && is_coverage_report(&dir_entry.path()) {
+        dir_entry.client_state = FileKind::Coverage;
+        dir_entry.read_children_path = None;
+    }
+}
+
+/// Walk the project, tagging its files. What its Makefile builds is a deliverable, whatever the
+/// rules say.
 pub fn id_temporary_files(
     dir: &std::path::Path,
 ) -> impl Iterator<Item = Result<CustomDirEnt, jwalk::Error>> {
-    let walk_dir = jwalk::WalkDirGeneric::<CustomState>::new(dir).process_read_dir(
-        |_depth, _path, _read_dir_state, children| {
+    let deliverables = super::makefile::deliverables(dir);
+    let walk_dir = crate::walk::walk_dir::<CustomState, _>(
+        dir,
+        crate::walk::Scope::Clean,
+        move |_depth, _path, _read_dir_state, children| {
             children
                 .iter_mut()
                 .filter_map(|dir_ent_result| match dir_ent_result {
                     Ok(dir_entry) if dir_entry.file_type().is_file() => Some(dir_entry),
                     _ => None,
                 })
-                .for_each(tag_file);
+                .for_each(|dir_entry| tag_file(dir_entry, &deliverables));
+            children
+                .iter_mut()
+                .filter_map(|dir_ent_result| match dir_ent_result {
+                    Ok(dir_entry) if dir_entry.file_type().is_dir() => Some(dir_entry),
+                    _ => None,
+                })
+                .for_each(tag_dir);
             children.retain(|dir_entry_result| {
                 dir_entry_result.as_ref().map(is_to_retain).unwrap_or(false)
             });
         },
     );
 
-    // INFO: afterwards, we can filter out the folders
+    // INFO: afterwards, we can filter out the folders, but coverage reports
     walk_dir.into_iter().filter(|elem| {
         elem.as_ref()
-            .map(|dir_entry| dir_entry.file_type().is_file())
+            .map(|dir_entry| {
+                dir_entry.file_type().is_file() || dir_entry.client_state == FileKind::Coverage
+            })
             .unwrap_or(false)
     })
 }
@@ -0,0 +1,340 @@
+//! Just enough of make to learn what a simple Makefile produces: variable assignments, rules
+//! whose target is a variable, and includes. Conditionals are not evaluated, both branches are
+//! read.
+
+/// Makefiles that make looks for, in its order.
+static MAKEFILES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+
+/// Variables conventionally holding what the Makefile builds.
+static DELIVERABLE_VARIABLES: [&str; 6] = ["NAME", "TARGET", "TARGETS", "BIN", "EXEC", "PROGRAM"];
+
+/// Includes deeper than this are not followed.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Variable references deeper than this are not expanded.
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+#[derive(Debug, Default)]
+struct Makefile {
+    variables: std::collections::HashMap<String, String>,
+    /// Targets of rules that reference a variable, unexpanded.
+    variable_targets: Vec<String>,
+    phony: Vec<String>,
+    read: std::collections::HashSet<std::path::PathBuf>,
+}
+
+/// Files that the Makefile of the project says it builds, if it has one.
+pub fn deliverables(project: &std::path::Path) -> std::collections::HashSet<std::path::PathBuf> {
+    let Some(path) = MAKEFILES
+        .iter()
+        .map(|name| project.join(name))
+        .find(|path| path.is_file())
+    else {
+        return Default::default();
+    };
+    let mut makefile = Makefile::default();
+    makefile.read_file(project, &path, 0);
+    makefile
+        .deliverable_names()
+        .into_iter()
+        .map(|name| project.join(name))
+        .collect()
+}
+
+impl Makefile {
+    /// Read `path` and the files it includes, which are relative to the project like make does.
+    fn read_file(&mut self, project: &std::path::Path, path: &std::path::Path, depth: usize) {
+        if depth > MAX_INCLUDE_DEPTH || !self.read.insert(path.to_owned()) {
+            return;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        for include in self.parse(&content) {
+            for word in self.expand(&include, 0).split_whitespace() {
+                self.read_file(project, &project.join(word), depth + 1);
+            }
+        }
+    }
+
+    /// Take in the assignments and rules of `content`, returns the arguments of its includes.
+    fn parse(&mut self, content: &str) -> Vec<String> {
+        let mut includes = vec![];
+        let mut in_define = false;
+        for line in logical_lines(content) {
+            // INFO: recipes, they don't define anything.
+            if line.starts_with('\t') {
+                continue;
+            }
+            let line = strip_comment(&line);
+            let line = line.trim();
+            if in_define {
+                in_define = line != "endef";
+                continue;
+            }
+            if line.starts_with("define ") {
+                in_define = true;
+                continue;
+            }
+            if let Some(args) = ["include ", "-include ", "sinclude "]
+                .iter()
+                .find_map(|directive| line.strip_prefix(directive))
+            {
+                includes.push(args.to_owned());
+                continue;
+            }
+            let line = ["export ", "override "].iter().fold(line, |line, prefix| {
+                line.strip_prefix(prefix).unwrap_or(line)
+            });
+            match split_statement(line) {
+                Some(Statement::Assignment { name, op, value }) => self.assign(name, op, value),
+                Some(Statement::Rule {
+                    targets,
+                    prerequisites,
+                }) => self.rule(targets, prerequisites),
+                None => {}
+            }
+        }
+        includes
+    }
+
+    fn assign(&mut self, name: &str, op: &str, value: &str) {
+        match op {
+            ":=" | "::=" => {
+                let value = self.expand(value, 0);
+                self.variables.insert(name.to_owned(), value);
+            }
+            "?=" => {
+                self.variables
+                    .entry(name.to_owned())
+                    .or_insert_with(|| value.to_owned());
+            }
+            "+=" => {
+                let entry = self.variables.entry(name.to_owned()).or_default();
+                if !entry.is_empty() {
+                    entry.push(' ');
+                }
+                entry.push_str(value);
+            }
+            // INFO: `!=` runs a shell command, we don't.
+            "!=" => {}
+            _ => {
+                self.variables.insert(name.to_owned(), value.to_owned());
+            }
+        }
+    }
+
+    fn rule(&mut self, targets: &str, prerequisites: &str) {
+        if targets == ".PHONY" {
+            self.phony.push(prerequisites.to_owned());
+            return;
+        }
+        if targets.contains("$(") || targets.contains("${") {
+            self.variable_targets.push(targets.to_owned());
+        }
+    }
+
+    /// Replace the `$(VAR)` and `${VAR}` references of `value`, unknown variables and functions
+    /// expand to nothing.
+    fn expand(&self, value: &str, depth: usize) -> String {
+        if depth > MAX_EXPANSION_DEPTH {
+            return String::new();
+        }
+        let mut expanded = String::new();
+        let mut rest = value;
+        while let Some(dollar) = rest.find('$') {
+            expanded.push_str(&rest[..dollar]);
+            rest = &rest[dollar + 1..];
+            let close = match rest.chars().next() {
+                Some('(') => ')',
+                Some('{') => '}',
+                Some('$') => {
+                    expanded.push('$');
+                    rest = &rest[1..];
+                    continue;
+                }
+                // INFO: single letter variables, automatic ones mostly.
+                Some(letter) => {
+                    rest = &rest[letter.len_utf8()..];
+                    continue;
+                }
+                None => break,
+            };
+            let Some(end) = rest.find(close) else {
+                break;
+            };
+            let name = &rest[1..end];
+            rest = &rest[end + 1..];
+            if let Some(value) = self.variables.get(name) {
+                expanded.push_str(&self.expand(value, depth + 1));
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    fn deliverable_names(&self) -> Vec<String> {
+        let phony: Vec<String> = self
+            .phony
+            .iter()
+            .flat_map(|phony| {
+                self.expand(phony, 0)
+                    .split_whitespace()
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let mut names: Vec<String> = DELIVERABLE_VARIABLES
+            .iter()
+            .filter_map(|name| self.variables.get(*name))
+            .chain(&self.variable_targets)
+            .flat_map(|value| {
+                self.expand(value, 0)
+                    .split_whitespace()
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|name| is_file_name(name) && !phony.contains(name))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Words that look like files rather than flags, patterns or objects.
+fn is_file_name(word: &str) -> bool {
+    !word.starts_with('-') && !word.contains('%') && !word.contains('$') && !word.ends_with(".o")
+}
+
+/// Lines of `content` with their continuations joined.
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for line in content.lines() {
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                current.push_str(continued);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        // INFO: `\#` is a literal hash.
+        Some(hash) if hash == 0 || line.as_bytes()[hash - 1] != b'\\' => &line[..hash],
+        _ => line,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Statement<'a> {
+    Assignment {
+        name: &'a str,
+        op: &'a str,
+        value: &'a str,
+    },
+    Rule {
+        targets: &'a str,
+        prerequisites: &'a str,
+    },
+}
+
+/// Tell an assignment from a rule by what comes first: an assignment operator or a colon.
+fn split_statement(line: &str) -> Option<Statement<'_>> {
+    let bytes = line.as_bytes();
+    let mut depth = 0;
+    for (idx, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => depth -= 1,
+            _ if depth > 0 => {}
+            b'=' => {
+                let op_start = line[..idx]
+                    .rfind(|c: char| !matches!(c, ':' | '?' | '+' | '!'))
+                    .map(|last| last + 1)
+                    .unwrap_or(0);
+                return Some(Statement::Assignment {
+                    name: line[..op_start].trim(),
+                    op: &line[op_start..=idx],
+                    value: line[idx + 1..].trim(),
+                });
+            }
+            b':' if !line[idx..].trim_start_matches(':').starts_with('=') => {
+                return Some(Statement::Rule {
+                    targets: line[..idx].trim(),
+                    prerequisites: line[idx..].trim_start_matches(':').trim(),
+                });
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests_parse {
+    use super::*;
+
+    fn names_of(content: &str) -> Vec<String> {
+        let mut makefile = Makefile::default();
+        makefile.parse(content);
+        makefile.deliverable_names()
+    }
+
+    #[test]
+    fn reads_conventional_variables() {
+        let content = "NAME\t= my_ls\nSRC = main.c \\\n\tls.c\nOBJ = $(SRC:.c=.o)\n\nall: $(NAME)\n\n$(NAME): $(OBJ)\n\t$(CC) -o $(NAME) $(OBJ)\n";
+        assert_eq!(names_of(content), vec!["my_ls"]);
+    }
+
+    #[test]
+    fn reads_rules_on_variables() {
+        let content = "BUILD_DIR := build\nOUT = $(BUILD_DIR)/server # the binary\n${OUT}: main.o\n\tcc -o $@ $^\n%.o: %.c\n";
+        assert_eq!(names_of(content), vec!["build/server"]);
+    }
+
+    #[test]
+    fn tells_assignments_from_rules() {
+        assert_eq!(
+            split_statement("NAME ?= a.out"),
+            Some(Statement::Assignment {
+                name: "NAME",
+                op: "?=",
+                value: "a.out"
+            })
+        );
+        assert_eq!(
+            split_statement("CFLAGS ::= -Wall"),
+            Some(Statement::Assignment {
+                name: "CFLAGS",
+                op: "::=",
+                value: "-Wall"
+            })
+        );
+        assert_eq!(
+            split_statement("$(NAME): CFLAGS += -g"),
+            Some(Statement::Rule {
+                targets: "$(NAME)",
+                prerequisites: "CFLAGS += -g"
+            })
+        );
+        assert_eq!(split_statement("endif"), None);
+    }
+
+    #[test]
+    fn leaves_out_phony_targets_and_flags() {
+        let content = "TARGETS = prog check\nBIN = -o\n.PHONY: check\n";
+        assert_eq!(names_of(content), vec!["prog"]);
+    }
+}
@@ -0,0 +1,196 @@
+use super::plan;
+use crate::disk_usage::DiskUsage;
+
+/// How the outcome of a run is shown on the standard output.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Progress and a summary for humans
+    #[default]
+    Human,
+    /// The report as JSON, progress goes to stderr
+    Json,
+}
+
+/// Outcome of a `candela clean` run, for scripts.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct RunReport {
+    /// Run id in the journal, when something was recorded there.
+    pub run_id: Option<String>,
+    pub dry_run: bool,
+    pub n_processed: u32,
+    pub n_cleaned: u32,
+    /// Space freed by the run.
+    pub usage_difference: DiskUsage,
+    pub projects: Vec<ProjectReport>,
+    /// Global caches, when `--caches` was given.
+    pub caches: Vec<CacheReport>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Cleaned,
+    NothingToClean,
+    /// The user said no when asked to confirm.
+    Declined,
+    /// Left out of the selection of `--interactive`.
+    NotSelected,
+    /// Had activity more recently than `--older-than` allows.
+    SkippedRecent,
+    /// Only planned, by `--dry-run`.
+    Planned,
+    /// Nothing could be removed, the errors tell why.
+    Failed,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ProjectReport {
+    pub path: std::path::PathBuf,
+    /// Names of the kinds, as in the configuration file.
+    pub langs: Vec<String>,
+    pub status: Status,
+    /// Every action of the plan, as shown to the user.
+    pub planned: Vec<String>,
+    /// Space that the plan would free, when it was computed.
+    pub reclaimable: Option<DiskUsage>,
+    pub removed: Vec<std::path::PathBuf>,
+    /// Usage of the project and of its artefacts living elsewhere.
+    pub size_before: Option<DiskUsage>,
+    pub size_after: Option<DiskUsage>,
+    pub commands: Vec<CommandReport>,
+    pub errors: Vec<String>,
+}
+
+/// How the effect of a command was obtained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Execution {
+    /// The command ran and succeeded.
+    Tool,
+    /// What the command frees was removed by candela.
+    Native,
+    /// The command could not do its job and there was nothing to fall back on.
+    Failed,
+    /// The command wasn't run when trashing: what it deletes couldn't be restored, and it has no
+    /// fallback to move to the trash instead.
+    Skipped,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CommandReport {
+    pub command: String,
+    pub execution: Execution,
+    /// Why the command wasn't run or didn't do its job.
+    pub reason: Option<String>,
+    /// None when the command could not be run or was killed by a signal.
+    pub exit_status: Option<i32>,
+    pub stderr: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CacheReport {
+    pub name: String,
+    pub path: std::path::PathBuf,
+    pub status: Status,
+    pub size_before: DiskUsage,
+    /// Space that pruning would free, measured before pruning.
+    pub reclaimable: DiskUsage,
+    /// Only once the cache is pruned.
+    pub size_after: Option<DiskUsage>,
+    /// Number of entries removed.
+    pub n_removed: u32,
+    pub errors: Vec<String>,
+}
+
+impl CommandReport {
+    /// Report of a command that didn't run yet.
+    pub fn new(cmd: &std::process::Command) -> Self {
+        Self {
+            command: super::command::command_line(cmd.get_program(), cmd.get_args()),
+            execution: Execution::Tool,
+            reason: None,
+            exit_status: None,
+            stderr: String::new(),
+        }
+    }
+}
+
+impl ProjectReport {
+    pub fn new(plan: &plan::Plan, status: Status) -> Self {
+        Self {
+            path: plan.path.clone(),
+            langs: plan
+                .langs
+                .iter()
+                .map(|lang| lang.config_name().to_owned())
+                .collect(),
+            status,
+            planned: plan
+                .actions
+                .iter()
+                .map(|action| action.to_string())
+                .collect(),
+            reclaimable: None,
+            removed: vec![],
+            size_before: None,
+            size_after: None,
+            commands: vec![],
+            errors: vec![],
+        }
+    }
+}
+
+impl RunReport {
+    /// Fails on paths that aren't valid UTF-8.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut json = self.to_json()?;
+        json.push('\n');
+        std::fs::write(path, json)
+    }
+
+    pub fn with_status(&self, status: Status) -> impl Iterator<Item = &ProjectReport> {
+        self.projects
+            .iter()
+            .filter(move |project| project.status == status)
+    }
+}
+
+/// Table of the caches, with what was freed once pruned or what would be before.
+pub fn print_caches(caches: &[CacheReport], out: &mut impl std::io::Write) -> std::io::Result<()> {
+    if caches.is_empty() {
+        return writeln!(out, "No known cache found.");
+    }
+    let pruned = caches.iter().any(|cache| cache.size_after.is_some());
+    let name_width = caches
+        .iter()
+        .map(|cache| cache.name.len())
+        .max()
+        .unwrap_or(0);
+    writeln!(
+        out,
+        "{:>10}  {:>11}  {:<name_width$}  PATH",
+        "SIZE",
+        if pruned { "FREED" } else { "RECLAIMABLE" },
+        "CACHE"
+    )?;
+    for cache in caches {
+        let freed = match cache.size_after {
+            Some(size_after) => cache.size_before.saturating_sub(size_after),
+            None if pruned => DiskUsage::default(),
+            None => cache.reclaimable,
+        };
+        writeln!(
+            out,
+            "{:>10}  {:>11}  {:<name_width$}  {}",
+            humansize::format_size(cache.size_before.allocated, humansize::DECIMAL),
+            humansize::format_size(freed.allocated, humansize::DECIMAL),
+            cache.name,
+            cache.path.display()
+        )?;
+    }
+    Ok(())
+}
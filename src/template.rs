@@ -156,16 +156,25 @@ impl Executor {
                 });
             map
         };
-        let ans = inquire::MultiSelect::new("Files to apply", rel_file_string)
-            .with_all_selected_by_default()
-            .prompt();
+        let ui = crate::ui::options();
+        // INFO: every file is selected by default, that is what --yes takes.
+        let ans = if ui.yes {
+            Ok(rel_file_string)
+        } else if !ui.input {
+            eprintln!("no input, no files are applied (use --yes to apply them all)");
+            return;
+        } else {
+            inquire::MultiSelect::new("Files to apply", rel_file_string)
+                .with_all_selected_by_default()
+                .prompt()
+        };
         if let Ok(ans) = ans {
             ans.iter().for_each(|file_str| {
                 let dest = std::path::PathBuf::from(file_str);
                 let src = map.get(file_str).unwrap();
                 if let Err(err) = std::fs::copy(src, &dest) {
                     eprintln!("{err}");
-                } else {
+                } else if !ui.quiet {
                     println!("{} -> {}", &src.display(), &dest.display());
                 }
             });
@@ -0,0 +1,277 @@
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+#[derive(clap::Args, Debug)]
+pub struct TrashArgs {
+    /// Move files to the trash instead of deleting them
+    #[arg(long, overrides_with = "no_trash")]
+    pub trash: bool,
+
+    /// Delete files for good, even if the configuration asks for the trash
+    #[arg(long)]
+    pub no_trash: bool,
+}
+
+impl TrashArgs {
+    /// The command line flags win over the configuration.
+    pub fn deletion(&self) -> Deletion {
+        if self.trash || (!self.no_trash && crate::config::get().trash) {
+            Deletion::Trash
+        } else {
+            Deletion::Unlink
+        }
+    }
+}
+
+/// How files are gotten rid of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Deletion {
+    #[default]
+    Unlink,
+    Trash,
+}
+
+impl Deletion {
+    /// Get rid of a file or a whole directory. Returns the path it was moved to when it was
+    /// trashed.
+    pub fn remove(self, path: &std::path::Path) -> std::io::Result<Option<std::path::PathBuf>> {
+        match self {
+            Self::Unlink => {
+                remove(path)?;
+                Ok(None)
+            }
+            Self::Trash => trash(path).map(Some),
+        }
+    }
+}
+
+fn remove(path: &std::path::Path) -> std::io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Move a file or directory to the trash as described by the freedesktop.org Trash
+/// specification. Files on the same filesystem as the home trash go there, others go to the
+/// trash at the top of their mount point and, if that one is unusable, are copied to the home
+/// trash. Returns the path of the file in the trash.
+pub fn trash(path: &std::path::Path) -> std::io::Result<std::path::PathBuf> {
+    let path = std::path::absolute(path)?;
+    let dev = path.symlink_metadata()?.dev();
+    let home_trash = home_trash();
+    if let Ok(home_trash) = &home_trash {
+        if device_of_nearest_existing(home_trash) == Some(dev) {
+            return trash_into(&path, home_trash, None);
+        }
+    }
+    let topdir = mount_point(&path, dev);
+    topdir_trash(&topdir)
+        .and_then(|trash_dir| trash_into(&path, &trash_dir, Some(&topdir)))
+        .or_else(|_| trash_into(&path, &home_trash?, None))
+}
+
+fn home_trash() -> std::io::Result<std::path::PathBuf> {
+    crate::xdg::data_home()
+        .map(|dir| dir.join("Trash"))
+        .ok_or_else(crate::xdg::no_home)
+}
+
+fn device_of_nearest_existing(path: &std::path::Path) -> Option<u64> {
+    path.ancestors()
+        .find_map(|ancestor| ancestor.metadata().ok())
+        .map(|meta| meta.dev())
+}
+
+/// Last ancestor of `path` that is still on the device `dev`.
+fn mount_point(path: &std::path::Path, dev: u64) -> std::path::PathBuf {
+    let mut top = path.parent().unwrap_or(path);
+    while let Some(parent) = top.parent() {
+        match parent.metadata() {
+            Ok(meta) if meta.dev() == dev => top = parent,
+            _ => break,
+        }
+    }
+    top.to_owned()
+}
+
+/// Pick the trash of a mount point: `$topdir/.Trash/$uid` when the administrator set up a
+/// `.Trash` directory with the sticky bit, `$topdir/.Trash-$uid` otherwise.
+fn topdir_trash(topdir: &std::path::Path) -> std::io::Result<std::path::PathBuf> {
+    // SAFETY: getuid cannot fail.
+    let uid = unsafe { libc::getuid() };
+    let shared = topdir.join(".Trash");
+    if let Ok(meta) = shared.symlink_metadata() {
+        if meta.is_dir() && meta.permissions().mode() & 0o1000 != 0 {
+            let trash_dir = shared.join(uid.to_string());
+            if create_trash_dirs(&trash_dir).is_ok() {
+                return Ok(trash_dir);
+            }
+        }
+    }
+    let trash_dir = topdir.join(format!(".Trash-{uid}"));
+    create_trash_dirs(&trash_dir)?;
+    Ok(trash_dir)
+}
+
+fn create_trash_dirs(trash_dir: &std::path::Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(trash_dir.join("files"))?;
+    builder.create(trash_dir.join("info"))
+}
+
+/// Move `path` into `trash_dir`. The `.trashinfo` file is created first, and exclusively, to
+/// reserve a name that no other process can take. Paths of files trashed in a mount point's
+/// trash are recorded relative to its `topdir`.
+fn trash_into(
+    path: &std::path::Path,
+    trash_dir: &std::path::Path,
+    topdir: Option<&std::path::Path>,
+) -> std::io::Result<std::path::PathBuf> {
+    create_trash_dirs(trash_dir)?;
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let recorded_path = topdir
+        .and_then(|topdir| path.strip_prefix(topdir).ok())
+        .unwrap_or(path);
+    let date = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let content = trash_info(recorded_path, &date);
+
+    let mut n: u32 = 1;
+    let (info_path, dest) = loop {
+        let mut name = file_name.to_owned();
+        if n > 1 {
+            name.push(format!(".{n}"));
+        }
+        let dest = trash_dir.join("files").join(&name);
+        let mut info_name = name;
+        info_name.push(".trashinfo");
+        let info_path = trash_dir.join("info").join(info_name);
+        n += 1;
+        if dest.symlink_metadata().is_ok() {
+            continue;
+        }
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(mut info_file) => {
+                info_file.write_all(content.as_bytes())?;
+                break (info_path, dest);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    };
+
+    let moved = match std::fs::rename(path, &dest) {
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            copy_recursively(path, &dest).and_then(|_| remove(path))
+        }
+        res => res,
+    };
+    if let Err(err) = moved {
+        let _ = std::fs::remove_file(&info_path);
+        return Err(err);
+    }
+    Ok(dest)
+}
+
+/// Move a file back from the trash to where it was, and drop its `.trashinfo`. Fails if
+/// something already exists at the original path.
+pub fn restore(trashed: &std::path::Path, original: &std::path::Path) -> std::io::Result<()> {
+    if original.symlink_metadata().is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "something already exists at the original path",
+        ));
+    }
+    if let Some(parent) = original.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(trashed, original) {
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            copy_recursively(trashed, original).and_then(|_| remove(trashed))?
+        }
+        res => res?,
+    }
+    if let (Some(files_dir), Some(file_name)) = (trashed.parent(), trashed.file_name()) {
+        if let Some(trash_dir) = files_dir.parent() {
+            let mut info_name = file_name.to_owned();
+            info_name.push(".trashinfo");
+            let _ = std::fs::remove_file(trash_dir.join("info").join(info_name));
+        }
+    }
+    Ok(())
+}
+
+/// Content of the `.trashinfo` file for a trashed path.
+fn trash_info(original_path: &std::path::Path, deletion_date: &str) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original_path),
+        deletion_date
+    )
+}
+
+/// Percent-encode a path the way URIs are, leaving the separators alone.
+fn encode_path(path: &std::path::Path) -> String {
+    let mut out = String::new();
+    for byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(byte) {
+            out.push(*byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Copy a file, symlink or whole directory, for when it can't just be renamed across
+/// filesystems.
+fn copy_recursively(src: &std::path::Path, dest: &std::path::Path) -> std::io::Result<()> {
+    let meta = src.symlink_metadata()?;
+    if meta.is_symlink() {
+        std::os::unix::fs::symlink(std::fs::read_link(src)?, dest)
+    } else if meta.is_dir() {
+        std::fs::create_dir(dest)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(dest, meta.permissions())
+    } else {
+        std::fs::copy(src, dest).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests_trash_info {
+    use super::{encode_path, trash_info};
+
+    #[test]
+    fn plain_path() {
+        let path = std::path::Path::new("/home/me/repo/a.out");
+        assert_eq!(encode_path(path), "/home/me/repo/a.out");
+    }
+
+    #[test]
+    fn reserved_characters() {
+        let path = std::path::Path::new("/home/me/my file%.txt");
+        assert_eq!(encode_path(path), "/home/me/my%20file%25.txt");
+    }
+
+    #[test]
+    fn content() {
+        let path = std::path::Path::new("repo/target");
+        assert_eq!(
+            trash_info(path, "2024-03-01T10:20:30"),
+            "[Trash Info]\nPath=repo/target\nDeletionDate=2024-03-01T10:20:30\n"
+        );
+    }
+}
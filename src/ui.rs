@@ -0,0 +1,147 @@
+use std::io::IsTerminal;
+
+/// How candela talks to the user, for every subcommand. Without a terminal, prompts and progress
+/// are turned off as if `--no-input` and `--no-progress` were given.
+#[derive(clap::Args, Clone, Copy, Debug, Default)]
+pub struct UiArgs {
+    /// Answer yes to every confirmation, and take the default selection without prompting: every
+    /// file of a template, no projects in clean --interactive and no duplicates
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Never prompt: confirmations are declined unless --yes is given, selections are skipped
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Only print errors and what was asked for (reports, listings)
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Don't show spinners
+    #[arg(long, global = true)]
+    pub no_progress: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UiOptions {
+    pub yes: bool,
+    /// Whether the user can be prompted.
+    pub input: bool,
+    pub quiet: bool,
+    /// Whether spinners can be drawn on stdout, and on stderr.
+    pub progress: (bool, bool),
+}
+
+impl Default for UiOptions {
+    fn default() -> Self {
+        Self {
+            yes: false,
+            input: true,
+            quiet: false,
+            progress: (true, true),
+        }
+    }
+}
+
+static OPTIONS: once_cell::sync::OnceCell<UiOptions> = once_cell::sync::OnceCell::new();
+
+impl UiArgs {
+    /// Make these the options of the run.
+    pub fn apply(&self) {
+        let progress = !self.no_progress && !self.quiet;
+        let _ = OPTIONS.set(UiOptions {
+            yes: self.yes,
+            input: !self.no_input && std::io::stdin().is_terminal(),
+            quiet: self.quiet,
+            progress: (
+                progress && std::io::stdout().is_terminal(),
+                progress && std::io::stderr().is_terminal(),
+            ),
+        });
+    }
+}
+
+pub fn options() -> UiOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// Ask a yes or no question. With `--yes` the answer is yes without asking; when the user can't
+/// be asked it is no.
+pub fn confirm(message: &str, default: bool) -> bool {
+    let options = options();
+    if options.yes {
+        return true;
+    }
+    if !options.input {
+        eprintln!("{message} no (no input, use --yes to agree)");
+        return false;
+    }
+    inquire::Confirm::new(message)
+        .with_default(default)
+        .prompt()
+        .unwrap_or(false)
+}
+
+/// A spinner that is only drawn when progress can be shown. Its final messages are printed as
+/// plain lines otherwise, and not at all with `--quiet`, failures aside.
+pub struct Progress {
+    spinner: Option<spinoff::Spinner>,
+    stream: spinoff::Streams,
+}
+
+impl Progress {
+    pub fn new<S, T>(frames: S, msg: T, stream: spinoff::Streams) -> Self
+    where
+        S: Into<spinoff::spinners::SpinnerFrames>,
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+        let (on_stdout, on_stderr) = options().progress;
+        let shown = match stream {
+            spinoff::Streams::Stdout => on_stdout,
+            spinoff::Streams::Stderr => on_stderr,
+        };
+        Self {
+            spinner: shown.then(|| spinoff::Spinner::new_with_stream(frames, msg, None, stream)),
+            stream,
+        }
+    }
+
+    pub fn update_text<T>(&mut self, msg: T)
+    where
+        T: Into<std::borrow::Cow<'static, str>>,
+    {
+        if let Some(spinner) = &mut self.spinner {
+            spinner.update_text(msg);
+        }
+    }
+
+    pub fn success(&mut self, msg: &str) {
+        match &mut self.spinner {
+            Some(spinner) => spinner.success(msg),
+            None if !options().quiet => {
+                use std::io::Write;
+                let _ = writeln!(self.stream.get_stream(), "✓ {msg}");
+            }
+            None => {}
+        }
+    }
+
+    pub fn fail(&mut self, msg: &str) {
+        match &mut self.spinner {
+            Some(spinner) => spinner.fail(msg),
+            None => eprintln!("✗ {msg}"),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(spinner) = &mut self.spinner {
+            spinner.stop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if let Some(spinner) = &mut self.spinner {
+            spinner.clear();
+        }
+    }
+}
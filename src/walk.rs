@@ -0,0 +1,256 @@
+// INFO: bounds of the walks, shared by the subcommands that walk directory trees. Not a doc
+// comment, clap would take it as the about of the subcommands flattening it.
+#[derive(clap::Args, Clone, Copy, Debug, Default)]
+pub struct WalkArgs {
+    /// Don't enter directories more than N levels below the given paths, or below each project
+    /// when looking for files to clean in it
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Stay on the filesystem of each walked path, mount points are not entered
+    #[arg(short = 'x', long)]
+    pub one_file_system: bool,
+
+    /// Follow symbolic links to directories when looking for projects or files
+    #[arg(long, overrides_with = "no_follow_symlinks")]
+    pub follow_symlinks: bool,
+
+    /// Don't follow symbolic links (default)
+    #[arg(long)]
+    pub no_follow_symlinks: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WalkOptions {
+    pub max_depth: Option<usize>,
+    pub one_file_system: bool,
+    pub follow_symlinks: bool,
+}
+
+static OPTIONS: once_cell::sync::OnceCell<WalkOptions> = once_cell::sync::OnceCell::new();
+
+impl WalkArgs {
+    /// Make these the options of every walk of the run.
+    pub fn apply(&self) {
+        let _ = OPTIONS.set(WalkOptions {
+            max_depth: self.max_depth,
+            one_file_system: self.one_file_system,
+            follow_symlinks: self.follow_symlinks && !self.no_follow_symlinks,
+        });
+    }
+}
+
+pub fn options() -> WalkOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+/// A directory containing this file is left alone by every walk, along with everything below it.
+static KEEP_MARKER: &str = ".candela-keep";
+
+/// Tells if the directory is marked to be left alone.
+pub fn is_kept(dir: &std::path::Path) -> bool {
+    dir.join(KEEP_MARKER).exists()
+}
+
+/// Tells if `root` or a directory below it is marked to be left alone, without following
+/// symlinks.
+pub fn holds_kept(root: &std::path::Path) -> bool {
+    jwalk::WalkDir::new(root)
+        .skip_hidden(false)
+        .into_iter()
+        .filter_map(|dir_ent| dir_ent.ok())
+        .any(|dir_ent| dir_ent.file_name() == KEEP_MARKER)
+}
+
+/// What a walk is for, which tells which of the options apply to it. Filesystems are never
+/// crossed with `--one-file-system`, whatever the scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Looking for projects or files under the paths given by the user: every option applies.
+    Search,
+    /// Looking for files to remove under a project: bounded by `--max-depth`, counted from the
+    /// project rather than from the given paths, but symlinks are never followed so that nothing
+    /// outside of the project gets removed.
+    Clean,
+    /// Finding the artefacts of a project or measuring it: the whole tree is walked, without
+    /// following symlinks.
+    Project,
+}
+
+/// A walk of `root` honouring the walk options. `process_read_dir` is called once children on
+/// other filesystems have been pruned, and marked directories removed: they are not even yielded.
+pub fn walk_dir<C, F>(
+    root: &std::path::Path,
+    scope: Scope,
+    process_read_dir: F,
+) -> jwalk::WalkDirGeneric<C>
+where
+    C: jwalk::ClientState,
+    F: Fn(
+            Option<usize>,
+            &std::path::Path,
+            &mut C::ReadDirState,
+            &mut Vec<jwalk::Result<jwalk::DirEntry<C>>>,
+        ) + Send
+        + Sync
+        + 'static,
+{
+    walk_dir_with(root, scope, options(), process_read_dir)
+}
+
+/// [`walk_dir`] with these options rather than those of the run.
+fn walk_dir_with<C, F>(
+    root: &std::path::Path,
+    scope: Scope,
+    options: WalkOptions,
+    process_read_dir: F,
+) -> jwalk::WalkDirGeneric<C>
+where
+    C: jwalk::ClientState,
+    F: Fn(
+            Option<usize>,
+            &std::path::Path,
+            &mut C::ReadDirState,
+            &mut Vec<jwalk::Result<jwalk::DirEntry<C>>>,
+        ) + Send
+        + Sync
+        + 'static,
+{
+    use std::os::unix::fs::MetadataExt;
+
+    let root_dev = root.metadata().map(|meta| meta.dev()).ok();
+    let mut walk_dir = jwalk::WalkDirGeneric::<C>::new(root);
+    walk_dir = walk_dir.follow_links(scope == Scope::Search && options.follow_symlinks);
+    if let (Some(max_depth), Scope::Search | Scope::Clean) = (options.max_depth, scope) {
+        walk_dir = walk_dir.max_depth(max_depth + 1);
+    }
+    walk_dir.process_read_dir(move |depth, path, read_dir_state, children| {
+        // INFO: only the root can be marked here, marked children are never read.
+        if is_kept(path) {
+            children.clear();
+        }
+        children.retain(|dir_ent_res| {
+            dir_ent_res.as_ref().map_or(true, |dir_ent| {
+                !dir_ent.file_type().is_dir() || !is_kept(&dir_ent.path())
+            })
+        });
+        if options.one_file_system {
+            children
+                .iter_mut()
+                .filter_map(|dir_ent_res| dir_ent_res.as_mut().ok())
+                .filter(|dir_ent| dir_ent.file_type().is_dir())
+                .filter(|dir_ent| {
+                    dir_ent
+                        .metadata()
+                        .is_ok_and(|meta| Some(meta.dev()) != root_dev)
+                })
+                .for_each(|dir_ent| dir_ent.read_children_path = None);
+        }
+        process_read_dir(depth, path, read_dir_state, children);
+    })
+}
+
+/// [`walk_dir`] without client state, for walks of a project.
+pub fn walk_project<F>(root: &std::path::Path, process_read_dir: F) -> jwalk::WalkDir
+where
+    F: Fn(
+            Option<usize>,
+            &std::path::Path,
+            &mut (),
+            &mut Vec<jwalk::Result<jwalk::DirEntry<((), ())>>>,
+        ) + Send
+        + Sync
+        + 'static,
+{
+    walk_dir(root, Scope::Project, process_read_dir)
+}
+
+#[cfg(test)]
+mod tests_walk_dir {
+    use super::*;
+    use crate::fixture::Tree;
+
+    /// Paths yielded by the walk, relative to the root.
+    fn walked(
+        root: &std::path::Path,
+        scope: Scope,
+        options: WalkOptions,
+    ) -> Vec<std::path::PathBuf> {
+        let mut walked: Vec<_> =
+            walk_dir_with::<((), ()), _>(root, scope, options, |_, _, _, _| {})
+                .into_iter()
+                .filter_map(|dir_ent| dir_ent.ok())
+                .filter(|dir_ent| dir_ent.depth > 0)
+                .map(|dir_ent| dir_ent.path().strip_prefix(root).unwrap().to_owned())
+                .collect();
+        walked.sort();
+        walked
+    }
+
+    fn paths(rels: &[&str]) -> Vec<std::path::PathBuf> {
+        rels.iter().map(std::path::PathBuf::from).collect()
+    }
+
+    #[test]
+    fn max_depth_counts_entered_directories() {
+        let tree = Tree::new();
+        tree.file("a/f1", "");
+        tree.file("a/b/f2", "");
+        tree.file("a/b/c/f3", "");
+        let options = WalkOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+
+        // INFO: `a` is one level below the root and is entered, `a/b` is seen but not entered.
+        let expected = paths(&["a", "a/b", "a/f1"]);
+        assert_eq!(walked(&tree.root, Scope::Search, options), expected);
+        assert_eq!(walked(&tree.root, Scope::Clean, options), expected);
+        let options = WalkOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(walked(&tree.root, Scope::Search, options), paths(&["a"]));
+    }
+
+    #[test]
+    fn projects_are_walked_whole() {
+        let tree = Tree::new();
+        tree.file("a/b/c/f3", "");
+        let options = WalkOptions {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            walked(&tree.root, Scope::Project, options),
+            paths(&["a", "a/b", "a/b/c", "a/b/c/f3"])
+        );
+    }
+
+    #[test]
+    fn symlinks_only_followed_when_searching() {
+        let tree = Tree::new();
+        tree.file("outside/f", "");
+        tree.file("root/f", "");
+        std::os::unix::fs::symlink(tree.path("outside"), tree.path("root/link")).unwrap();
+        let root = tree.path("root");
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            walked(&root, Scope::Search, options),
+            paths(&["f", "link", "link/f"])
+        );
+        assert_eq!(walked(&root, Scope::Clean, options), paths(&["f", "link"]));
+        assert_eq!(
+            walked(&root, Scope::Project, options),
+            paths(&["f", "link"])
+        );
+        assert_eq!(
+            walked(&root, Scope::Search, WalkOptions::default()),
+            paths(&["f", "link"])
+        );
+    }
+}
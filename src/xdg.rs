@@ -0,0 +1,46 @@
+/// The user's home directory, None when `$HOME` isn't set (e.g. in systemd units).
+pub fn home() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+/// The error of what can't be found without a home directory.
+pub fn no_home() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, "HOME is not set")
+}
+
+/// Read an XDG base directory from the environment, falling back on its default location under
+/// the home directory. Relative paths in the variables are invalid per the specification and
+/// ignored. None without either.
+fn base_dir(var: &str, default_in_home: &[&str]) -> Option<std::path::PathBuf> {
+    if let Some(value) = std::env::var_os(var) {
+        let path = std::path::PathBuf::from(value);
+        if path.is_absolute() {
+            return Some(path);
+        }
+    }
+    let mut path = home()?;
+    default_in_home.iter().for_each(|comp| path.push(comp));
+    Some(path)
+}
+
+/// `$XDG_CONFIG_HOME`, usually `~/.config`.
+pub fn config_home() -> Option<std::path::PathBuf> {
+    base_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// `$XDG_CACHE_HOME`, usually `~/.cache`.
+pub fn cache_home() -> Option<std::path::PathBuf> {
+    base_dir("XDG_CACHE_HOME", &[".cache"])
+}
+
+/// `$XDG_DATA_HOME`, usually `~/.local/share`.
+pub fn data_home() -> Option<std::path::PathBuf> {
+    base_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// `$XDG_STATE_HOME`, usually `~/.local/state`.
+pub fn state_home() -> Option<std::path::PathBuf> {
+    base_dir("XDG_STATE_HOME", &[".local", "state"])
+}
@@ -0,0 +1,67 @@
+//! `candela clean --caches` lists the caches and prunes nothing until the user agrees.
+
+mod common;
+
+use common::{parse_stdout, Sandbox};
+
+fn with_cargo_registry(name: &str) -> (Sandbox, std::path::PathBuf) {
+    let sandbox = Sandbox::new(name);
+    let entry = sandbox.file(
+        "home/.cargo/registry/src/index/serde-1.0.0/src/lib.rs",
+        "pub fn serialize() {}\n",
+    );
+    (sandbox, entry)
+}
+
+#[test]
+fn declined_without_input() {
+    let (sandbox, entry) = with_cargo_registry("caches-declined");
+
+    let output = sandbox.candela(&["clean", "--caches", "--format", "json"]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["caches"][0]["name"], "Cargo registry");
+    assert_eq!(report["caches"][0]["status"], "declined");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("about to prune:"), "{stderr}");
+    assert!(stderr.contains("Cargo registry"), "{stderr}");
+    assert!(entry.exists());
+}
+
+#[test]
+fn pruned_once_agreed() {
+    let (sandbox, entry) = with_cargo_registry("caches-agreed");
+
+    let output = sandbox.candela(&["clean", "--caches", "--format", "json", "--yes"]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["caches"][0]["status"], "cleaned");
+    assert!(!entry.exists());
+}
+
+#[test]
+fn only_measured_on_dry_runs() {
+    let (sandbox, entry) = with_cargo_registry("caches-dry-run");
+
+    let output = sandbox.candela(&[
+        "clean",
+        "--caches",
+        "--format",
+        "json",
+        "--yes",
+        "--dry-run",
+    ]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["caches"][0]["status"], "planned");
+    assert!(report["caches"][0]["reclaimable"]["apparent"].as_u64() > Some(0));
+    assert!(entry.exists());
+}
+
+#[test]
+fn age_without_equals_is_refused() {
+    let (sandbox, entry) = with_cargo_registry("caches-age-without-equals");
+
+    let output = sandbox.candela(&["clean", "--yes", "--caches", "90d"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--caches=90d"), "{stderr}");
+    assert!(entry.exists());
+}
@@ -0,0 +1,30 @@
+//! Cleanup commands of custom kinds run in the project, project-local scripts included.
+
+mod common;
+
+use common::{parse_stdout, Sandbox};
+
+#[test]
+fn project_local_scripts() {
+    let sandbox = Sandbox::new("commands-local");
+    sandbox.file(
+        "home/.config/candela/config.toml",
+        r#"
+[[projects]]
+name = "script"
+markers = ["clean.sh"]
+command = ["./clean.sh"]
+"#,
+    );
+    let script = sandbox.file("proj/clean.sh", "#!/bin/sh\nrm -rf out\n");
+    std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    let out = sandbox.file("proj/out/artefact", "");
+    let dir = sandbox.root.join("proj");
+
+    let output = sandbox.candela(&["clean", "--yes", "--format", "json", dir.to_str().unwrap()]);
+    let report = parse_stdout(&output);
+    let command = &report["projects"][0]["commands"][0];
+    assert_eq!(command["command"], "./clean.sh");
+    assert_eq!(command["execution"], "tool");
+    assert!(!out.exists());
+}
@@ -0,0 +1,60 @@
+//! Helpers shared by the integration tests.
+
+// INFO: each test crate includes this module and only uses some of it.
+#![allow(dead_code)]
+
+pub struct Sandbox {
+    pub root: std::path::PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("candela-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    pub fn file(&self, rel: &str, content: &str) -> std::path::PathBuf {
+        let path = self.root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Run candela with a home of its own, so that nothing of the user's is read or written, its
+    /// caches included.
+    pub fn candela(&self, args: &[&str]) -> std::process::Output {
+        self.command(args).output().unwrap()
+    }
+
+    /// The command of [`Sandbox::candela`], to tweak its environment.
+    pub fn command(&self, args: &[&str]) -> std::process::Command {
+        let home = self.root.join("home");
+        let mut cmd = std::process::Command::new(env!("CARGO_BIN_EXE_candela"));
+        cmd.args(args)
+            .env("HOME", &home)
+            .env("CARGO_HOME", home.join(".cargo"))
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_STATE_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .env_remove("npm_config_cache")
+            .env_remove("YARN_CACHE_FOLDER")
+            .env_remove("PIP_CACHE_DIR")
+            .env_remove("GOCACHE")
+            .stdin(std::process::Stdio::null());
+        cmd
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// The JSON report that candela wrote on its standard output.
+pub fn parse_stdout(output: &std::process::Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(&stdout).unwrap_or_else(|err| panic!("{err} in:\n{stdout}"))
+}
@@ -0,0 +1,25 @@
+//! candela runs where the user has no home directory, like in systemd units.
+
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn without_home() {
+    let sandbox = Sandbox::new("without-home");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox
+        .command(&["clean", "--yes", dir.to_str().unwrap()])
+        .env_remove("HOME")
+        .env_remove("CARGO_HOME")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    // INFO: nowhere to record the run, which is said rather than fatal.
+    assert!(stderr.contains("journal: HOME is not set"), "{stderr}");
+    assert!(!object.exists());
+}
@@ -0,0 +1,32 @@
+//! With `--format json`, the standard output of `candela clean` is the report and nothing else.
+
+mod common;
+
+use common::{parse_stdout, Sandbox};
+
+#[test]
+fn confirmed_plans_stay_out_of_stdout() {
+    let sandbox = Sandbox::new("json-confirmed");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    sandbox.file("c/main.c", "int main(void) { return 0; }\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox.candela(&["clean", "--format", "json", "-y", dir.to_str().unwrap()]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["projects"][0]["status"], "cleaned");
+    assert!(!object.exists());
+}
+
+#[test]
+fn declined_plans_stay_out_of_stdout() {
+    let sandbox = Sandbox::new("json-declined");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox.candela(&["clean", "--format", "json", dir.to_str().unwrap()]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["projects"][0]["status"], "declined");
+    assert!(object.exists());
+}
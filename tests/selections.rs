@@ -0,0 +1,45 @@
+//! With `--yes`, selections take their default without prompting, so nothing is picked for
+//! removal.
+
+mod common;
+
+use common::{parse_stdout, Sandbox};
+
+#[test]
+fn interactive_clean_selects_no_projects() {
+    let sandbox = Sandbox::new("selections-clean");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox.candela(&[
+        "clean",
+        "--interactive",
+        "--yes",
+        "--format",
+        "json",
+        dir.to_str().unwrap(),
+    ]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["projects"][0]["status"], "not_selected");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--yes takes the default selection"),
+        "{stderr}"
+    );
+    assert!(object.exists());
+}
+
+#[test]
+fn duplicates_are_kept() {
+    let sandbox = Sandbox::new("selections-duplicates");
+    let first = sandbox.file("dups/a.txt", "same content");
+    let second = sandbox.file("dups/b.txt", "same content");
+    let dir = sandbox.root.join("dups");
+
+    let output = sandbox.candela(&["duplicates", "--yes", dir.to_str().unwrap()]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("--yes keeps every file"), "{stderr}");
+    assert!(first.exists() && second.exists());
+}
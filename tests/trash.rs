@@ -0,0 +1,104 @@
+//! With `--trash`, nothing is deleted for good, not even by the cleanup commands of custom kinds.
+
+mod common;
+
+use common::{parse_stdout, Sandbox};
+
+#[test]
+fn commands_without_fallback_are_skipped() {
+    let sandbox = Sandbox::new("trash-commands");
+    sandbox.file(
+        "home/.config/candela/config.toml",
+        r#"
+[[projects]]
+name = "mark"
+markers = ["build.mark"]
+command = ["rm", "-rf", "out"]
+"#,
+    );
+    sandbox.file("proj/build.mark", "");
+    let out = sandbox.file("proj/out/artefact", "");
+    let dir = sandbox.root.join("proj");
+
+    let output = sandbox.candela(&[
+        "clean",
+        "--trash",
+        "--yes",
+        "--format",
+        "json",
+        dir.to_str().unwrap(),
+    ]);
+    let report = parse_stdout(&output);
+    let command = &report["projects"][0]["commands"][0];
+    assert_eq!(command["command"], "rm -rf out");
+    assert_eq!(command["execution"], "skipped");
+    assert!(out.exists());
+}
+
+#[test]
+fn failed_removals_are_no_cleaning() {
+    let sandbox = Sandbox::new("trash-failed");
+    // INFO: the home trash can't be created under a file.
+    sandbox.file("home/.local/share", "");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox.candela(&[
+        "clean",
+        "--trash",
+        "--yes",
+        "--format",
+        "json",
+        dir.to_str().unwrap(),
+    ]);
+    let report = parse_stdout(&output);
+    assert_eq!(report["n_cleaned"], 0);
+    assert_eq!(report["projects"][0]["status"], "failed");
+    assert_eq!(report["projects"][0]["errors"].as_array().unwrap().len(), 1);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("failed to clean 1"), "{stderr}");
+    assert!(object.exists());
+}
+
+#[test]
+fn undo_restores_trashed_removals() {
+    let sandbox = Sandbox::new("trash-undo");
+    sandbox.file("c/Makefile", "NAME = prog\nall: $(NAME)\n");
+    let object = sandbox.file("c/main.o", "not really an object");
+    let dir = sandbox.root.join("c");
+
+    let output = sandbox.candela(&[
+        "clean",
+        "--trash",
+        "--yes",
+        "--format",
+        "json",
+        dir.to_str().unwrap(),
+    ]);
+    let report = parse_stdout(&output);
+    assert!(!object.exists());
+    let run_id = report["run_id"].as_str().unwrap();
+
+    let output = sandbox.candela(&["undo", run_id]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        std::fs::read_to_string(&object).unwrap(),
+        "not really an object"
+    );
+
+    // INFO: nothing is left in the trash the second time.
+    let output = sandbox.candela(&["undo", run_id]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn undo_refuses_unknown_runs() {
+    let sandbox = Sandbox::new("trash-undo-unknown");
+    sandbox.file("home/.local/state/x.jsonl", "");
+
+    for run_id in ["20240301-102030-4242", "../x"] {
+        let output = sandbox.candela(&["undo", run_id]);
+        assert!(!output.status.success(), "{run_id}");
+    }
+}